@@ -0,0 +1,331 @@
+//! Annotating files with the revisions that last changed each line.
+//!
+//! [`crate::tree::Tree::annotate_iter`] only yields the revision id of each
+//! line. The [`Annotator`] in this module resolves those to dotted revision
+//! numbers, authors and dates, fetching the metadata of each revision only
+//! once. The resulting [`AnnotatedLine`]s can be rendered like `brz annotate`
+//! does with [`render`], or serialized with serde, e.g. to JSON.
+use crate::branch::{Branch, GenericBranch, PyBranch};
+use crate::error::Error;
+use crate::log::LogDirection;
+use crate::repository::{GenericRepository, Repository};
+use crate::tree::PyTree;
+use crate::RevisionId;
+use chrono::TimeZone;
+use pyo3::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A line of a file, together with the revision that last changed it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnotatedLine {
+    /// The revision that last changed the line.
+    pub revision_id: RevisionId,
+    /// The dotted revision number of the revision, e.g. `"3"` or `"2.1.4"`,
+    /// or `None` if the revision is not in the ancestry of the branch.
+    pub revno: Option<String>,
+    /// The revision number of the mainline revision that introduced the
+    /// line, which differs from `revno` for lines from merged revisions.
+    pub mainline_revno: Option<u32>,
+    /// The primary author of the revision.
+    pub author: Option<String>,
+    /// The time of the revision, in seconds since the Unix epoch.
+    pub timestamp: Option<f64>,
+    /// The timezone offset of the revision, in seconds east of UTC.
+    pub timezone: Option<i32>,
+    /// The contents of the line, including the line ending.
+    #[serde(serialize_with = "serialize_lossy")]
+    pub line: Vec<u8>,
+}
+
+fn serialize_lossy<S: serde::Serializer>(line: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&String::from_utf8_lossy(line))
+}
+
+impl AnnotatedLine {
+    /// Get the time of the revision, in its own timezone.
+    pub fn datetime(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        let tz = chrono::FixedOffset::east_opt(self.timezone?)?;
+        tz.timestamp_opt(self.timestamp? as i64, 0).single()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RevisionInfo {
+    author: String,
+    timestamp: f64,
+    timezone: i32,
+}
+
+/// Annotates files using the history of a branch.
+///
+/// Revision numbers are computed once when the annotator is created, and
+/// revision metadata is cached, so annotating many files with the same
+/// annotator is cheap.
+pub struct Annotator {
+    revnos: HashMap<RevisionId, (String, u32)>,
+    repository: GenericRepository,
+    revisions: HashMap<RevisionId, Option<RevisionInfo>>,
+}
+
+impl Annotator {
+    /// Create an annotator for the history of a branch.
+    ///
+    /// # Arguments
+    ///
+    /// * `branch` - The branch whose revision numbers to use
+    pub fn new(branch: &dyn PyBranch) -> Result<Self, Error> {
+        let branch = Python::attach(|py| GenericBranch::from(branch.to_object(py)));
+        let mut revnos = HashMap::new();
+        let mut mainline = 0;
+        // Merged revisions follow the mainline revision that merged them.
+        for entry in branch.merge_sorted_revisions(None, None, LogDirection::Reverse)? {
+            if entry.merge_depth == 0 {
+                mainline = entry.revno[0];
+            }
+            revnos.insert(entry.node.clone(), (entry.revno_str(), mainline));
+        }
+        Ok(Annotator {
+            revnos,
+            repository: branch.repository(),
+            revisions: HashMap::new(),
+        })
+    }
+
+    fn fetch_revisions(&mut self, revision_ids: Vec<RevisionId>) {
+        let missing = revision_ids
+            .into_iter()
+            .filter(|r| !r.is_reserved() && !self.revisions.contains_key(r))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return;
+        }
+        for (revision_id, revision) in self.repository.iter_revisions(missing) {
+            let info = revision.map(|revision| RevisionInfo {
+                author: revision
+                    .get_apparent_authors()
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| revision.committer.clone()),
+                timestamp: revision.timestamp,
+                timezone: revision.timezone,
+            });
+            self.revisions.insert(revision_id, info);
+        }
+    }
+
+    /// Annotate a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The tree containing the file
+    /// * `path` - The path of the file
+    ///
+    /// # Returns
+    ///
+    /// The lines of the file, with the revision that last changed each line
+    pub fn annotate(
+        &mut self,
+        tree: &dyn PyTree,
+        path: &Path,
+    ) -> Result<Vec<AnnotatedLine>, Error> {
+        let lines = tree
+            .annotate_iter(path, None)?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut revision_ids = lines.iter().map(|(r, _)| r.clone()).collect::<Vec<_>>();
+        revision_ids.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        revision_ids.dedup();
+        self.fetch_revisions(revision_ids);
+        Ok(lines
+            .into_iter()
+            .map(|(revision_id, line)| {
+                let revno = self.revnos.get(&revision_id);
+                let info = self.revisions.get(&revision_id).cloned().flatten();
+                AnnotatedLine {
+                    revno: revno.map(|(dotted, _)| dotted.clone()),
+                    mainline_revno: revno.map(|(_, mainline)| *mainline),
+                    author: info.as_ref().map(|i| i.author.clone()),
+                    timestamp: info.as_ref().map(|i| i.timestamp),
+                    timezone: info.as_ref().map(|i| i.timezone),
+                    revision_id,
+                    line,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Annotate a file using the history of a branch.
+///
+/// Use an [`Annotator`] directly to annotate several files.
+///
+/// # Arguments
+///
+/// * `tree` - The tree containing the file
+/// * `path` - The path of the file
+/// * `branch` - The branch whose revision numbers to use
+///
+/// # Returns
+///
+/// The lines of the file, with the revision that last changed each line
+pub fn annotate(
+    tree: &dyn PyTree,
+    path: &Path,
+    branch: &dyn PyBranch,
+) -> Result<Vec<AnnotatedLine>, Error> {
+    Annotator::new(branch)?.annotate(tree, path)
+}
+
+/// Render annotated lines the way `brz annotate` does.
+///
+/// Each line is prefixed with the revision number and the author's email
+/// address (or name), truncated to 7 characters. With `long`, the author is
+/// shown in full and followed by the date of the revision, like
+/// `brz annotate --long`.
+///
+/// # Arguments
+///
+/// * `lines` - The annotated lines
+/// * `full` - Whether to annotate every line, like `brz annotate --all`.
+///   Otherwise the annotation is only shown when it differs from the
+///   previous line.
+/// * `long` - Whether to show the full author and the date
+pub fn render(lines: &[AnnotatedLine], full: bool, long: bool) -> String {
+    let annotations = lines
+        .iter()
+        .map(|line| {
+            let revno = line.revno.clone().unwrap_or_else(|| "?".to_string());
+            let author = line
+                .author
+                .as_deref()
+                .map(|author| {
+                    // Prefer the email address, like brz does.
+                    match (author.find('<'), author.rfind('>')) {
+                        (Some(start), Some(end)) if start < end => &author[start + 1..end],
+                        _ => author,
+                    }
+                })
+                .unwrap_or("")
+                .to_string();
+            let date = line
+                .datetime()
+                .map(|d| d.format("%Y%m%d").to_string())
+                .unwrap_or_default();
+            (revno, author, date)
+        })
+        .collect::<Vec<_>>();
+    let mut revno_width = annotations.iter().map(|a| a.0.len()).max().unwrap_or(0);
+    if !long {
+        revno_width = revno_width.min(12);
+    }
+    let revno_width = revno_width.max(3);
+    let author_width = annotations.iter().map(|a| a.1.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    let mut previous = None;
+    for (line, (revno, author, date)) in lines.iter().zip(&annotations) {
+        let prefix = if long {
+            format!(
+                "{:<rw$} {:<aw$} {:>8}",
+                revno,
+                author,
+                date,
+                rw = revno_width,
+                aw = author_width
+            )
+        } else {
+            // Overlong revision numbers are cut short and marked with '>'.
+            let revno = if revno.len() > revno_width {
+                format!("{}>", &revno[..revno_width - 1])
+            } else {
+                revno.clone()
+            };
+            let author = author.chars().take(7).collect::<String>();
+            format!("{:<rw$} {:<7}", revno, author, rw = revno_width)
+        };
+        if full || previous != Some(&line.revision_id) {
+            out.push_str(&prefix);
+        } else {
+            out.push_str(&" ".repeat(prefix.chars().count()));
+        }
+        out.push_str(" | ");
+        out.push_str(&String::from_utf8_lossy(&line.line));
+        if !line.line.ends_with(b"\n") {
+            out.push('\n');
+        }
+        previous = Some(&line.revision_id);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+    use crate::tree::MutableTree;
+    use crate::workingtree::{GenericWorkingTree, WorkingTree};
+    use serial_test::serial;
+
+    fn commit(wt: &GenericWorkingTree, message: &str, committer: &str) -> RevisionId {
+        wt.build_commit()
+            .message(message)
+            .committer(committer)
+            // Midday UTC, so the date is the same in most timezones.
+            .timestamp(1699956000.0)
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn test_annotate() {
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(Path::new("."), &ControlDirFormat::default()).unwrap();
+        std::fs::write("a", b"one\ntwo\n").unwrap();
+        wt.add(&[Path::new("a")]).unwrap();
+        let r1 = commit(&wt, "Add a", "Alice <alice@example.com>");
+        std::fs::write("a", b"one\ntwo\nthree\n").unwrap();
+        let r2 = commit(&wt, "Change a", "Bob <bob@example.com>");
+
+        let branch = wt.branch();
+        let basis = wt.basis_tree().unwrap();
+        let lines = annotate(&basis, Path::new("a"), &branch).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].revision_id, r1);
+        assert_eq!(lines[0].revno.as_deref(), Some("1"));
+        assert_eq!(lines[0].mainline_revno, Some(1));
+        assert_eq!(
+            lines[0].author.as_deref(),
+            Some("Alice <alice@example.com>")
+        );
+        assert_eq!(lines[2].revision_id, r2);
+        assert_eq!(lines[2].revno.as_deref(), Some("2"));
+        assert_eq!(lines[2].line, b"three\n");
+
+        assert_eq!(
+            render(&lines, false, false),
+            concat!(
+                "1   alice@e | one\n",
+                "            | two\n",
+                "2   bob@exa | three\n",
+            )
+        );
+        assert!(render(&lines, true, false).starts_with("1   alice@e | one\n1   alice@e | two\n"));
+        assert_eq!(
+            render(&lines, false, true),
+            concat!(
+                "1   alice@example.com 20231114 | one\n",
+                "                               | two\n",
+                "2   bob@example.com   20231114 | three\n",
+            )
+        );
+
+        let json = serde_json::to_value(&lines[2]).unwrap();
+        assert_eq!(json["revno"], "2");
+        assert_eq!(json["author"], "Bob <bob@example.com>");
+        assert_eq!(json["line"], "three\n");
+        std::mem::drop(env);
+    }
+}
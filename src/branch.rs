@@ -183,6 +183,23 @@ pub trait Branch {
     ///
     /// `Ok(())` on success, or an error if the pull failed.
     fn pull(&self, source_branch: &dyn PyBranch, overwrite: Option<bool>) -> Result<(), Error>;
+    /// Pull from a source branch into this branch, copying only selected tags.
+    ///
+    /// # Parameters
+    ///
+    /// * `source_branch` - The branch to pull from.
+    /// * `overwrite` - Whether to overwrite this branch if it has diverged from the source.
+    /// * `tag_selector` - Selects the tags to copy.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an error if the pull failed.
+    fn pull_with_tags(
+        &self,
+        source_branch: &dyn PyBranch,
+        overwrite: Option<bool>,
+        tag_selector: crate::tags::TagSelector,
+    ) -> Result<(), Error>;
     /// Get the parent branch location.
     ///
     /// # Returns
@@ -332,6 +349,51 @@ pub trait Branch {
     ///
     /// The revision number, or an error if the revision ID is not in the branch.
     fn revision_id_to_revno(&self, revision_id: &RevisionId) -> Result<u32, Error>;
+    /// Convert a revision ID to its dotted revision number.
+    ///
+    /// # Parameters
+    ///
+    /// * `revision_id` - The revision ID to convert.
+    ///
+    /// # Returns
+    ///
+    /// The dotted revision number, e.g. `[2, 1, 3]` for `2.1.3`, or an error if
+    /// the revision ID is not in the ancestry of the branch.
+    fn revision_id_to_dotted_revno(&self, revision_id: &RevisionId) -> Result<Vec<u32>, Error>;
+    /// Get the revision ID for a dotted revision number.
+    ///
+    /// # Parameters
+    ///
+    /// * `revno` - The dotted revision number, e.g. `[2, 1, 3]` for `2.1.3`.
+    ///
+    /// # Returns
+    ///
+    /// The revision ID, or [`Error::NoSuchRevno`] if there is no such revision.
+    fn dotted_revno_to_revision_id(&self, revno: &[u32]) -> Result<RevisionId, Error>;
+    /// Get the revisions in the ancestry of the branch in merge-sorted order.
+    ///
+    /// The merge-sorted history is cached by the branch, so repeated calls
+    /// are cheap.
+    ///
+    /// # Parameters
+    ///
+    /// * `start_revision_id` - The newest revision to include, or None for the tip.
+    /// * `stop_revision_id` - The revision to stop at (exclusive), or None to
+    ///   include all older revisions.
+    /// * `direction` - The order of the returned entries. Unlike in
+    ///   [`crate::log::LogRequest`], [`crate::log::LogDirection::Forward`] is
+    ///   the plain reversal of the merge-sorted order: merged revisions are
+    ///   listed before the revision that merged them, without regard to depth.
+    ///
+    /// # Returns
+    ///
+    /// The merge-sorted revisions, with their dotted revision numbers.
+    fn merge_sorted_revisions(
+        &self,
+        start_revision_id: Option<&RevisionId>,
+        stop_revision_id: Option<&RevisionId>,
+        direction: crate::log::LogDirection,
+    ) -> Result<Vec<crate::tsort::MergeSortEntry<RevisionId>>, Error>;
     /// Check whether a revision number corresponds to a real revision.
     ///
     /// # Parameters
@@ -390,6 +452,26 @@ pub trait Branch {
         from_branch: &dyn Branch,
         last_revision: Option<&RevisionId>,
     ) -> Result<(), Error>;
+    /// Fetch revisions and the revisions of selected tags from another branch.
+    ///
+    /// The tags themselves are not copied; use [`crate::tags::Tags::merge_to`]
+    /// for that.
+    ///
+    /// # Parameters
+    ///
+    /// * `from_branch` - The branch to fetch revisions from.
+    /// * `last_revision` - The last revision to fetch, or None to fetch all.
+    /// * `tag_selector` - Selects the tags whose revisions to fetch.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an error if the fetch failed.
+    fn fetch_with_tags(
+        &self,
+        from_branch: &dyn Branch,
+        last_revision: Option<&RevisionId>,
+        tag_selector: crate::tags::TagSelector,
+    ) -> Result<(), Error>;
     /// Update this branch to match the master branch.
     ///
     /// This is used when the branch is bound to synchronize changes.
@@ -611,6 +693,28 @@ impl<T: PyBranch> Branch for T {
         })
     }
 
+    fn pull_with_tags(
+        &self,
+        source_branch: &dyn PyBranch,
+        overwrite: Option<bool>,
+        tag_selector: crate::tags::TagSelector,
+    ) -> Result<(), Error> {
+        Python::attach(|py| {
+            let kwargs = PyDict::new(py);
+            if let Some(overwrite) = overwrite {
+                kwargs.set_item("overwrite", overwrite)?;
+            }
+            kwargs.set_item("tag_selector", py_tag_selector(py, tag_selector.into())?)?;
+            self.to_object(py).call_method(
+                py,
+                "pull",
+                (&source_branch.to_object(py),),
+                Some(&kwargs),
+            )?;
+            Ok(())
+        })
+    }
+
     fn get_parent(&self) -> Option<String> {
         Python::attach(|py| {
             self.to_object(py)
@@ -814,6 +918,75 @@ impl<T: PyBranch> Branch for T {
         })
     }
 
+    fn revision_id_to_dotted_revno(&self, revision_id: &RevisionId) -> Result<Vec<u32>, Error> {
+        Python::attach(|py| {
+            self.to_object(py)
+                .call_method1(py, "revision_id_to_dotted_revno", (revision_id.clone(),))?
+                .extract(py)
+                .map_err(Into::into)
+        })
+    }
+
+    fn dotted_revno_to_revision_id(&self, revno: &[u32]) -> Result<RevisionId, Error> {
+        pyo3::import_exception!(breezy.errors, NoSuchRevision);
+        Python::attach(|py| {
+            match self.to_object(py).call_method1(
+                py,
+                "dotted_revno_to_revision_id",
+                (pyo3::types::PyTuple::new(py, revno)?,),
+            ) {
+                Ok(revision_id) => Ok(revision_id.extract(py)?),
+                // The error carries the revision number rather than a revision id.
+                Err(e) if e.is_instance_of::<NoSuchRevision>(py) => {
+                    Err(Error::NoSuchRevno(revno.to_vec()))
+                }
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
+    fn merge_sorted_revisions(
+        &self,
+        start_revision_id: Option<&RevisionId>,
+        stop_revision_id: Option<&RevisionId>,
+        direction: crate::log::LogDirection,
+    ) -> Result<Vec<crate::tsort::MergeSortEntry<RevisionId>>, Error> {
+        let _lock = self.lock_read()?;
+        Python::attach(|py| {
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("start_revision_id", start_revision_id.cloned())?;
+            kwargs.set_item("stop_revision_id", stop_revision_id.cloned())?;
+            kwargs.set_item(
+                "direction",
+                match direction {
+                    crate::log::LogDirection::Reverse => "reverse",
+                    crate::log::LogDirection::Forward => "forward",
+                },
+            )?;
+            let entries = self.to_object(py).call_method(
+                py,
+                "iter_merge_sorted_revisions",
+                (),
+                Some(&kwargs),
+            )?;
+            entries
+                .bind(py)
+                .try_iter()?
+                .enumerate()
+                .map(|(sequence, entry)| {
+                    let (node, merge_depth, revno, end_of_merge) = entry?.extract()?;
+                    Ok(crate::tsort::MergeSortEntry {
+                        sequence,
+                        node,
+                        merge_depth,
+                        revno,
+                        end_of_merge,
+                    })
+                })
+                .collect()
+        })
+    }
+
     fn check_real_revno(&self, revno: u32) -> bool {
         Python::attach(|py| {
             self.to_object(py)
@@ -889,6 +1062,54 @@ impl<T: PyBranch> Branch for T {
         })
     }
 
+    fn fetch_with_tags(
+        &self,
+        from_branch: &dyn Branch,
+        last_revision: Option<&RevisionId>,
+        tag_selector: crate::tags::TagSelector,
+    ) -> Result<(), Error> {
+        let source = if let Some(gb) = from_branch.as_any().downcast_ref::<GenericBranch>() {
+            Python::attach(|py| gb.to_object(py))
+        } else if let Some(mb) = from_branch.as_any().downcast_ref::<MemoryBranch>() {
+            Python::attach(|py| mb.to_object(py))
+        } else {
+            return Err(Error::Other(pyo3::exceptions::PyTypeError::new_err(
+                "Branch must be a PyBranch",
+            )));
+        };
+        let tag_revisions = from_branch
+            .tags()?
+            .get_tag_dict()?
+            .into_iter()
+            .filter(|(tag, _)| tag_selector.matches(tag))
+            .map(|(_, revision_id)| revision_id)
+            .collect::<Vec<_>>();
+        let _lock = self.lock_write()?;
+        Python::attach(|py| {
+            let source = source.bind(py);
+            let source_repository = source.getattr("repository")?;
+            let target_repository = self.to_object(py).getattr(py, "repository")?;
+            // Tags may point at revisions that are not present (ghosts).
+            let tag_revisions =
+                source_repository.call_method1("has_revisions", (tag_revisions,))?;
+            let fetch = py.import("breezy.fetch")?;
+            let factory = fetch.getattr("FetchSpecFactory")?.call0()?;
+            factory.setattr("source_branch", source)?;
+            factory.setattr("source_branch_stop_revision_id", last_revision.cloned())?;
+            factory.setattr("source_repo", &source_repository)?;
+            factory.setattr("target_repo", &target_repository)?;
+            factory.setattr(
+                "target_repo_kind",
+                fetch.getattr("TargetRepoKinds")?.getattr("PREEXISTING")?,
+            )?;
+            factory.call_method1("add_revision_ids", (tag_revisions,))?;
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("fetch_spec", factory.call_method0("make_fetch_spec")?)?;
+            target_repository.call_method(py, "fetch", (source_repository,), Some(&kwargs))?;
+            Ok(())
+        })
+    }
+
     fn update(&self) -> Result<(), Error> {
         Python::attach(|py| {
             self.to_object(py).call_method0(py, "update")?;
@@ -1211,4 +1432,70 @@ mod tests {
 
         assert_eq!(branch.last_revision(), RevisionId::null());
     }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_dotted_revnos() {
+        use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+        use crate::log::LogDirection;
+        use crate::workingtree::{GenericWorkingTree, WorkingTree};
+        use std::path::Path;
+
+        let env = crate::testing::TestEnv::new();
+        let commit = |wt: &GenericWorkingTree, message: &str| {
+            wt.build_commit()
+                .message(message)
+                .allow_pointless(true)
+                .reporter(&crate::commit::NullCommitReporter::new())
+                .commit()
+                .unwrap()
+        };
+        std::fs::create_dir("trunk").unwrap();
+        let trunk = create_standalone_workingtree(Path::new("trunk"), &ControlDirFormat::default())
+            .unwrap();
+        let r1 = commit(&trunk, "One");
+        let feature_url =
+            url::Url::from_directory_path(std::env::current_dir().unwrap().join("feature"))
+                .unwrap();
+        let feature = trunk
+            .controldir()
+            .sprout(feature_url, None, Some(true), None, None)
+            .unwrap()
+            .open_workingtree()
+            .unwrap();
+        let f1 = commit(&feature, "Feature");
+        commit(&trunk, "Two");
+        trunk.merge_from_branch(&feature.branch(), None).unwrap();
+        let r3 = commit(&trunk, "Merge feature");
+
+        let branch = trunk.branch();
+        assert_eq!(branch.revision_id_to_dotted_revno(&r1).unwrap(), vec![1]);
+        assert_eq!(
+            branch.revision_id_to_dotted_revno(&f1).unwrap(),
+            vec![1, 1, 1]
+        );
+        assert_eq!(branch.dotted_revno_to_revision_id(&[1, 1, 1]).unwrap(), f1);
+        assert!(matches!(
+            branch.dotted_revno_to_revision_id(&[1, 2, 1]),
+            Err(Error::NoSuchRevno(r)) if r == vec![1, 2, 1]
+        ));
+
+        let entries = branch
+            .merge_sorted_revisions(None, None, LogDirection::Reverse)
+            .unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.revno_str()).collect::<Vec<_>>(),
+            vec!["3", "1.1.1", "2", "1"]
+        );
+        assert_eq!(entries[0].node, r3);
+        assert_eq!(entries[1].merge_depth, 1);
+        let entries = branch
+            .merge_sorted_revisions(None, Some(&r1), LogDirection::Forward)
+            .unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.revno_str()).collect::<Vec<_>>(),
+            vec!["2", "1.1.1", "3"]
+        );
+        std::mem::drop(env);
+    }
 }
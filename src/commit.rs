@@ -3,6 +3,12 @@
 //! This module provides types for reporting commit information and handling
 //! commit operations in version control systems.
 
+use crate::branch::Branch;
+use crate::delta::TreeDelta;
+use crate::error::Error;
+use crate::hooks::HookGuard;
+use crate::repository::Repository;
+use crate::RevisionId;
 use pyo3::prelude::*;
 
 /// A commit reporter that doesn't report anything.
@@ -177,6 +183,61 @@ impl std::fmt::Debug for ReportCommitToLog {
     }
 }
 
+/// A commit that is about to be made, as seen by a commit policy.
+#[derive(Debug, Clone)]
+pub struct PendingCommit {
+    /// The revision id the new revision will get.
+    pub revision_id: RevisionId,
+    /// The revision number the new revision will get.
+    pub revno: u32,
+    /// The parents of the new revision.
+    pub parent_ids: Vec<RevisionId>,
+    /// The changes being committed.
+    pub delta: TreeDelta,
+    /// The commit message.
+    pub message: String,
+    /// The committer of the new revision.
+    pub committer: String,
+    /// The revision properties of the new revision.
+    pub properties: std::collections::HashMap<String, String>,
+}
+
+/// Install a policy that can veto commits.
+///
+/// The policy is run for every commit before the branch tip is updated.
+/// Returning an error aborts the commit, which then fails with
+/// [`Error::CommitVetoed`] carrying the returned reason.
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the policy
+/// * `policy` - The policy, returning the reason for rejecting a commit
+///
+/// # Returns
+///
+/// A guard that uninstalls the policy when dropped
+pub fn install_commit_policy(
+    label: &str,
+    policy: impl Fn(&PendingCommit) -> Result<(), String> + Send + Sync + 'static,
+) -> Result<HookGuard, Error> {
+    crate::hooks::install_pre_commit_hook(label, move |params| {
+        // The new revision has already been written to the repository of the
+        // branch that was committed to, only the branch tip is still to change.
+        let branch = params.local.as_ref().unwrap_or(&params.master);
+        let revision = branch.repository().get_revision(&params.future_revid)?;
+        let pending = PendingCommit {
+            revision_id: params.future_revid.clone(),
+            revno: params.future_revno,
+            parent_ids: revision.parent_ids,
+            delta: params.tree_delta.clone(),
+            message: revision.message,
+            committer: revision.committer,
+            properties: revision.properties,
+        };
+        policy(&pending).map_err(Error::CommitVetoed)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,4 +251,54 @@ mod tests {
     fn test_report_commit_to_log() {
         ReportCommitToLog::new();
     }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_commit_policy() {
+        use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+        use crate::tree::MutableTree;
+        use crate::workingtree::WorkingTree;
+        use std::path::Path;
+
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(Path::new("."), &ControlDirFormat::default()).unwrap();
+        std::fs::write("a", b"a\n").unwrap();
+        wt.add(&[Path::new("a")]).unwrap();
+
+        let policy = install_commit_policy("breezyshim test policy", |pending| {
+            assert_eq!(pending.revno, 1);
+            assert_eq!(pending.delta.added.len(), 1);
+            if pending.message.contains("Signed-off-by:") {
+                Ok(())
+            } else {
+                Err(format!("{} is not signed off", pending.committer))
+            }
+        })
+        .unwrap();
+
+        let err = wt
+            .build_commit()
+            .message("Add a")
+            .committer("Joe Example <joe@example.com>")
+            .reporter(&NullCommitReporter::new())
+            .commit()
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::CommitVetoed(reason) if reason == "Joe Example <joe@example.com> is not signed off"),
+            "{:?}",
+            err
+        );
+        assert_eq!(wt.branch().last_revision(), RevisionId::null());
+
+        wt.build_commit()
+            .message("Add a\n\nSigned-off-by: Joe Example <joe@example.com>")
+            .committer("Joe Example <joe@example.com>")
+            .reporter(&NullCommitReporter::new())
+            .commit()
+            .unwrap();
+        assert_eq!(wt.branch().last_revision_info().0, 1);
+        std::mem::drop(policy);
+        std::mem::drop(env);
+    }
 }
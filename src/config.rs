@@ -3,9 +3,12 @@
 //! This module provides access to the Breezy configuration system.
 //! It allows reading and writing configuration values, and provides
 //! access to credential stores.
+use crate::error::Error;
 use crate::Result;
 use pyo3::conversion::IntoPyObjectExt;
 use pyo3::prelude::*;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 /// Parse a username string into name and email components.
 ///
@@ -173,6 +176,10 @@ impl ConfigStack {
         Self(o)
     }
 
+    pub(crate) fn as_pyobject(&self) -> &Py<PyAny> {
+        &self.0
+    }
+
     /// Get a configuration value from this stack.
     ///
     /// # Parameters
@@ -211,6 +218,634 @@ impl ConfigStack {
         })?;
         Ok(())
     }
+
+    /// Set a configuration value along with the policy of its section.
+    ///
+    /// The policy determines how the value applies to locations below the
+    /// section, and is only honoured by location stacks.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The configuration key to set.
+    /// * `value` - The value to set the configuration to.
+    /// * `policy` - The section policy for the option.
+    pub fn set_with_policy<T: ConfigValue>(
+        &self,
+        key: &str,
+        value: T,
+        policy: SectionPolicy,
+    ) -> Result<()> {
+        self.set(key, value)?;
+        let policy_key = format!("{}:policy", key);
+        match policy.as_str() {
+            Some(policy) => self.set(&policy_key, policy),
+            None => self.remove(&policy_key),
+        }
+    }
+
+    /// Remove a configuration value from the store this stack writes to.
+    ///
+    /// Removing an option that is not set is not an error.
+    pub fn remove(&self, key: &str) -> Result<()> {
+        Python::attach(|py| -> Result<()> {
+            match self.0.call_method1(py, "remove", (key,)) {
+                Ok(_) => Ok(()),
+                Err(e) if e.is_instance_of::<pyo3::exceptions::PyKeyError>(py) => Ok(()),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
+    /// Get the store that this stack writes to.
+    pub fn store(&self) -> Result<ConfigStore> {
+        Python::attach(|py| -> Result<ConfigStore> {
+            Ok(ConfigStore(self.0.getattr(py, "store")?))
+        })
+    }
+}
+
+impl Clone for ConfigStack {
+    fn clone(&self) -> Self {
+        Python::attach(|py| -> Self { Self(self.0.clone_ref(py)) })
+    }
+}
+
+impl<'py> IntoPyObject<'py> for ConfigStack {
+    type Target = PyAny;
+    type Output = Bound<'py, Self::Target>;
+    type Error = std::convert::Infallible;
+
+    fn into_pyobject(self, py: Python<'py>) -> std::result::Result<Self::Output, Self::Error> {
+        Ok(self.0.into_bound(py))
+    }
+}
+
+/// Policy for how an option in a location section applies to child locations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SectionPolicy {
+    /// The value applies unchanged to the location and all locations below it.
+    #[default]
+    None,
+    /// The value only applies to the exact location of the section.
+    NoRecurse,
+    /// The path of the child location relative to the section is appended to the value.
+    AppendPath,
+}
+
+impl SectionPolicy {
+    fn as_str(&self) -> Option<&'static str> {
+        match self {
+            SectionPolicy::None => None,
+            SectionPolicy::NoRecurse => Some("norecurse"),
+            SectionPolicy::AppendPath => Some("appendpath"),
+        }
+    }
+}
+
+impl std::str::FromStr for SectionPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "" | "none" => Ok(SectionPolicy::None),
+            "norecurse" => Ok(SectionPolicy::NoRecurse),
+            "appendpath" => Ok(SectionPolicy::AppendPath),
+            _ => Err(format!("Unknown section policy: {}", s)),
+        }
+    }
+}
+
+/// A configuration store, such as `breezy.conf` or `locations.conf`.
+///
+/// Stores hold the sections that configuration stacks read from. Writing to a
+/// store directly allows setting options in a section other than the one a
+/// stack would pick.
+pub struct ConfigStore(Py<PyAny>);
+
+impl From<Py<PyAny>> for ConfigStore {
+    fn from(obj: Py<PyAny>) -> Self {
+        ConfigStore(obj)
+    }
+}
+
+impl Clone for ConfigStore {
+    fn clone(&self) -> Self {
+        Python::attach(|py| -> Self { Self(self.0.clone_ref(py)) })
+    }
+}
+
+impl<'py> IntoPyObject<'py> for ConfigStore {
+    type Target = PyAny;
+    type Output = Bound<'py, Self::Target>;
+    type Error = std::convert::Infallible;
+
+    fn into_pyobject(self, py: Python<'py>) -> std::result::Result<Self::Output, Self::Error> {
+        Ok(self.0.into_bound(py))
+    }
+}
+
+impl ConfigStore {
+    /// Get the URL of the file backing this store.
+    pub fn external_url(&self) -> Result<String> {
+        Python::attach(|py| -> Result<String> {
+            Ok(self.0.call_method0(py, "external_url")?.extract(py)?)
+        })
+    }
+
+    /// List the ids of the sections in this store.
+    ///
+    /// The default section has the id `None`.
+    pub fn section_ids(&self) -> Result<Vec<Option<String>>> {
+        Python::attach(|py| -> Result<Vec<Option<String>>> {
+            let mut ret = vec![];
+            for section in self
+                .0
+                .call_method0(py, "get_sections")?
+                .bind(py)
+                .try_iter()?
+            {
+                // Stores yield (store, section) pairs.
+                let (_store, section): (Bound<PyAny>, Bound<PyAny>) = section?.extract()?;
+                ret.push(section.getattr("id")?.extract()?);
+            }
+            Ok(ret)
+        })
+    }
+
+    /// Get the raw value of an option in a section.
+    ///
+    /// # Parameters
+    ///
+    /// * `section_id` - The section to look in, or `None` for the default section.
+    /// * `key` - The option to look up.
+    pub fn get(&self, section_id: Option<&str>, key: &str) -> Result<Option<String>> {
+        Python::attach(|py| -> Result<Option<String>> {
+            for section in self
+                .0
+                .call_method0(py, "get_sections")?
+                .bind(py)
+                .try_iter()?
+            {
+                let (_store, section): (Bound<PyAny>, Bound<PyAny>) = section?.extract()?;
+                if section
+                    .getattr("id")?
+                    .extract::<Option<String>>()?
+                    .as_deref()
+                    != section_id
+                {
+                    continue;
+                }
+                let value = section.call_method1("get", (key,))?;
+                if value.is_none() {
+                    return Ok(None);
+                }
+                let value = self.0.call_method1(py, "unquote", (value,))?;
+                return Ok(Some(raw_to_string(value.bind(py))?));
+            }
+            Ok(None)
+        })
+    }
+
+    /// Set an option in a section and save the store.
+    ///
+    /// # Parameters
+    ///
+    /// * `section_id` - The section to write to, or `None` for the default section.
+    /// * `key` - The option to set.
+    /// * `value` - The value to set the option to.
+    pub fn set<T: ConfigValue>(&self, section_id: Option<&str>, key: &str, value: T) -> Result<()> {
+        Python::attach(|py| -> Result<()> {
+            let store = self.0.bind(py);
+            // Make sure changes made by other processes are not lost.
+            store.call_method0("unload")?;
+            let section = store.call_method1("get_mutable_section", (section_id,))?;
+            let value = store.call_method1("quote", (value.into_py_any(py)?,))?;
+            section.call_method1("set", (key, value))?;
+            store.call_method0("save")?;
+            Ok(())
+        })
+    }
+
+    /// Set the policy of an option in a section and save the store.
+    pub fn set_policy(
+        &self,
+        section_id: Option<&str>,
+        key: &str,
+        policy: SectionPolicy,
+    ) -> Result<()> {
+        let policy_key = format!("{}:policy", key);
+        match policy.as_str() {
+            Some(policy) => self.set(section_id, &policy_key, policy),
+            None => self.remove(section_id, &policy_key),
+        }
+    }
+
+    /// Remove an option from a section and save the store.
+    ///
+    /// Removing an option that is not set is not an error.
+    pub fn remove(&self, section_id: Option<&str>, key: &str) -> Result<()> {
+        Python::attach(|py| -> Result<()> {
+            let store = self.0.bind(py);
+            store.call_method0("unload")?;
+            let section = store.call_method1("get_mutable_section", (section_id,))?;
+            match section.call_method1("remove", (key,)) {
+                Ok(_) => {}
+                Err(e) if e.is_instance_of::<pyo3::exceptions::PyKeyError>(py) => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+            store.call_method0("save")?;
+            Ok(())
+        })
+    }
+}
+
+/// Parse a boolean the way Breezy does.
+///
+/// Accepts "yes", "no", "y", "n", "on", "off", "true", "false", "1" and "0",
+/// case-insensitively. Returns `None` for any other value.
+pub fn parse_bool(s: &str) -> Option<bool> {
+    match s.trim().to_lowercase().as_str() {
+        "yes" | "y" | "on" | "true" | "1" => Some(true),
+        "no" | "n" | "off" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse an integer that may carry an SI unit suffix, like "10K" or "5MB".
+///
+/// Mirrors Breezy's `int_SI_from_store`: the suffixes K, M and G are powers
+/// of 1000 and an optional trailing "b" is ignored.
+pub fn parse_int(s: &str) -> Option<i64> {
+    let (_, digits, unit) =
+        lazy_regex::regex_captures!(r"^\s*(-?\d+)\s*(?:([kmgKMG])[bB]?)?\s*$", s)?;
+    let value: i64 = digits.parse().ok()?;
+    let multiplier = match unit.to_lowercase().as_str() {
+        "" => 1,
+        "k" => 1_000,
+        "m" => 1_000_000,
+        "g" => 1_000_000_000,
+        _ => return None,
+    };
+    value.checked_mul(multiplier)
+}
+
+/// Parse a comma-separated list, as stored for Breezy list options.
+///
+/// Surrounding whitespace and quotes are stripped from each item and empty
+/// items are dropped.
+pub fn parse_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|item| item.trim().trim_matches('"').trim_matches('\'').to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Parse a duration.
+///
+/// Plain numbers are interpreted as seconds; the suffixes "s", "m", "h", "d"
+/// and "w" select seconds, minutes, hours, days and weeks respectively.
+pub fn parse_duration(s: &str) -> Option<chrono::Duration> {
+    let (_, number, unit) =
+        lazy_regex::regex_captures!(r"^\s*(\d+(?:\.\d+)?)\s*([smhdw]?)\s*$", s)?;
+    let number: f64 = number.parse().ok()?;
+    let seconds = match unit {
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        "d" => number * 86400.0,
+        "w" => number * 604800.0,
+        _ => return None,
+    };
+    chrono::Duration::try_milliseconds((seconds * 1000.0).round() as i64)
+}
+
+/// The type of a configuration option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionType {
+    /// A free-form string.
+    String,
+    /// A boolean.
+    Bool,
+    /// An integer, optionally with an SI unit suffix.
+    Int,
+    /// A comma-separated list of strings.
+    List,
+    /// A local filesystem path.
+    Path,
+    /// A URL.
+    Url,
+    /// A duration, in seconds unless a unit is given.
+    Duration,
+}
+
+/// A typed configuration value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionValue {
+    /// A string value.
+    String(String),
+    /// A boolean value.
+    Bool(bool),
+    /// An integer value.
+    Int(i64),
+    /// A list of strings.
+    List(Vec<String>),
+    /// A local filesystem path.
+    Path(PathBuf),
+    /// A URL.
+    Url(url::Url),
+    /// A duration.
+    Duration(chrono::Duration),
+}
+
+/// The definition of a configuration option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionDefinition {
+    /// The name of the option.
+    pub name: String,
+    /// The type of the option.
+    pub option_type: OptionType,
+    /// The default value, in its stored (string) form.
+    pub default: Option<String>,
+    /// Help text describing the option.
+    pub help: String,
+}
+
+impl OptionDefinition {
+    /// Create a new option definition without a default.
+    pub fn new(name: &str, option_type: OptionType, help: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            option_type,
+            default: None,
+            help: help.to_string(),
+        }
+    }
+
+    /// Set the default value of this option, in its stored (string) form.
+    pub fn with_default(mut self, default: &str) -> Self {
+        self.default = Some(default.to_string());
+        self
+    }
+
+    /// Parse a stored string into a typed value for this option.
+    pub fn parse(&self, value: &str) -> Result<OptionValue> {
+        let invalid = || Error::ConfigOptionValueError(self.name.clone(), value.to_string());
+        Ok(match self.option_type {
+            OptionType::String => OptionValue::String(value.to_string()),
+            OptionType::Bool => OptionValue::Bool(parse_bool(value).ok_or_else(invalid)?),
+            OptionType::Int => OptionValue::Int(parse_int(value).ok_or_else(invalid)?),
+            OptionType::List => OptionValue::List(parse_list(value)),
+            OptionType::Path => OptionValue::Path(expand_user(value)),
+            OptionType::Url => OptionValue::Url(value.parse().map_err(|_| invalid())?),
+            OptionType::Duration => {
+                OptionValue::Duration(parse_duration(value).ok_or_else(invalid)?)
+            }
+        })
+    }
+}
+
+/// A registry of known configuration options.
+///
+/// This mirrors Breezy's `option_registry`, so that configuration can be
+/// validated up front rather than when an option is first used.
+#[derive(Debug, Clone, Default)]
+pub struct OptionRegistry(BTreeMap<String, OptionDefinition>);
+
+impl OptionRegistry {
+    /// Create a new, empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the options known to Breezy.
+    ///
+    /// Options whose default is computed at runtime are registered without
+    /// a default.
+    pub fn from_breezy() -> Result<Self> {
+        Python::attach(|py| -> Result<Self> {
+            let m = py.import("breezy.config")?;
+            let registry = m.getattr("option_registry")?;
+            let list_option = m.getattr("ListOption")?;
+            let converters = [
+                ("bool_from_store", OptionType::Bool),
+                ("int_from_store", OptionType::Int),
+                ("int_SI_from_store", OptionType::Int),
+                ("list_from_store", OptionType::List),
+            ];
+            let mut ret = Self::new();
+            for key in registry.call_method0("keys")?.try_iter()? {
+                let key: String = key?.extract()?;
+                let option = registry.call_method1("get", (&key,))?;
+                let from_unicode = option.getattr("from_unicode")?;
+                let mut option_type = OptionType::String;
+                if option.is_instance(&list_option)? {
+                    option_type = OptionType::List;
+                } else {
+                    for (name, t) in converters {
+                        if let Ok(converter) = m.getattr(name) {
+                            if from_unicode.is(&converter) {
+                                option_type = t;
+                            }
+                        }
+                    }
+                }
+                let default = option.getattr("default")?;
+                let default = if default.is_callable() {
+                    None
+                } else {
+                    default.extract::<Option<String>>().ok().flatten()
+                };
+                let help = option
+                    .getattr("help")?
+                    .extract::<Option<String>>()?
+                    .unwrap_or_default();
+                ret.register(OptionDefinition {
+                    name: key,
+                    option_type,
+                    default,
+                    help,
+                });
+            }
+            Ok(ret)
+        })
+    }
+
+    /// Register an option, replacing any existing definition with the same name.
+    pub fn register(&mut self, option: OptionDefinition) {
+        self.0.insert(option.name.clone(), option);
+    }
+
+    /// Look up an option by name.
+    pub fn get(&self, name: &str) -> Option<&OptionDefinition> {
+        self.0.get(name)
+    }
+
+    /// Iterate over all registered options, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = &OptionDefinition> {
+        self.0.values()
+    }
+
+    /// Check that every registered option has a valid value in `config`.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if all options are valid, or the first
+    /// [`Error::ConfigOptionValueError`] encountered.
+    pub fn validate(&self, config: &dyn TypedConfig) -> Result<()> {
+        for option in self.iter() {
+            config.get_option(option)?;
+        }
+        Ok(())
+    }
+}
+
+fn expand_user(path: &str) -> PathBuf {
+    Python::attach(|py| -> PyResult<PathBuf> {
+        py.import("os.path")?
+            .call_method1("expanduser", (path,))?
+            .extract()
+    })
+    .unwrap_or_else(|_| PathBuf::from(path))
+}
+
+/// Convert a raw Python configuration value to its stored string form.
+///
+/// Lists are joined with commas, so that they can be parsed back by
+/// [`parse_list`].
+fn raw_to_string(value: &Bound<PyAny>) -> PyResult<String> {
+    if let Ok(s) = value.extract::<String>() {
+        Ok(s)
+    } else if let Ok(b) = value.extract::<bool>() {
+        Ok(if b { "True" } else { "False" }.to_string())
+    } else if let Ok(l) = value.extract::<Vec<String>>() {
+        Ok(l.join(","))
+    } else {
+        Ok(value.str()?.to_string())
+    }
+}
+
+/// Typed access to configuration values.
+///
+/// Values that Breezy has already converted (because the option is
+/// registered) are used as-is; plain strings are parsed.
+pub trait TypedConfig {
+    /// Get the raw Python value of an option, or `None` if it is unset.
+    fn get_raw(&self, key: &str) -> Result<Option<Py<PyAny>>>;
+
+    /// Get the value of an option as a string.
+    fn get_string(&self, key: &str) -> Result<Option<String>> {
+        Python::attach(|py| {
+            self.get_raw(key)?
+                .map(|v| Ok(raw_to_string(v.bind(py))?))
+                .transpose()
+        })
+    }
+
+    /// Get the value of an option as a boolean.
+    fn get_bool(&self, key: &str) -> Result<Option<bool>> {
+        Python::attach(|py| {
+            let Some(value) = self.get_raw(key)? else {
+                return Ok(None);
+            };
+            let value = value.bind(py);
+            if let Ok(b) = value.extract::<bool>() {
+                return Ok(Some(b));
+            }
+            let s = raw_to_string(value)?;
+            parse_bool(&s)
+                .map(Some)
+                .ok_or(Error::ConfigOptionValueError(key.to_string(), s))
+        })
+    }
+
+    /// Get the value of an option as an integer.
+    fn get_int(&self, key: &str) -> Result<Option<i64>> {
+        Python::attach(|py| {
+            let Some(value) = self.get_raw(key)? else {
+                return Ok(None);
+            };
+            let value = value.bind(py);
+            if let Ok(i) = value.extract::<i64>() {
+                return Ok(Some(i));
+            }
+            let s = raw_to_string(value)?;
+            parse_int(&s)
+                .map(Some)
+                .ok_or(Error::ConfigOptionValueError(key.to_string(), s))
+        })
+    }
+
+    /// Get the value of an option as a list of strings.
+    fn get_list(&self, key: &str) -> Result<Option<Vec<String>>> {
+        Python::attach(|py| {
+            let Some(value) = self.get_raw(key)? else {
+                return Ok(None);
+            };
+            let value = value.bind(py);
+            if let Ok(l) = value.extract::<Vec<String>>() {
+                if !value.is_instance_of::<pyo3::types::PyString>() {
+                    return Ok(Some(l));
+                }
+            }
+            Ok(Some(parse_list(&raw_to_string(value)?)))
+        })
+    }
+
+    /// Get the value of an option as a local path, with `~` expanded.
+    fn get_path(&self, key: &str) -> Result<Option<PathBuf>> {
+        Ok(self.get_string(key)?.map(|s| expand_user(&s)))
+    }
+
+    /// Get the value of an option as a URL.
+    fn get_url(&self, key: &str) -> Result<Option<url::Url>> {
+        self.get_string(key)?
+            .map(|s| {
+                s.parse()
+                    .map_err(|_| Error::ConfigOptionValueError(key.to_string(), s))
+            })
+            .transpose()
+    }
+
+    /// Get the value of an option as a duration.
+    fn get_duration(&self, key: &str) -> Result<Option<chrono::Duration>> {
+        self.get_string(key)?
+            .map(|s| parse_duration(&s).ok_or(Error::ConfigOptionValueError(key.to_string(), s)))
+            .transpose()
+    }
+
+    /// Get the typed value of a registered option, falling back to its default.
+    fn get_option(&self, option: &OptionDefinition) -> Result<Option<OptionValue>> {
+        let name = option.name.as_str();
+        let value = match option.option_type {
+            OptionType::String => self.get_string(name)?.map(OptionValue::String),
+            OptionType::Bool => self.get_bool(name)?.map(OptionValue::Bool),
+            OptionType::Int => self.get_int(name)?.map(OptionValue::Int),
+            OptionType::List => self.get_list(name)?.map(OptionValue::List),
+            OptionType::Path => self.get_path(name)?.map(OptionValue::Path),
+            OptionType::Url => self.get_url(name)?.map(OptionValue::Url),
+            OptionType::Duration => self.get_duration(name)?.map(OptionValue::Duration),
+        };
+        match (value, option.default.as_deref()) {
+            (Some(value), _) => Ok(Some(value)),
+            (None, Some(default)) => option.parse(default).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+impl TypedConfig for BranchConfig {
+    fn get_raw(&self, key: &str) -> Result<Option<Py<PyAny>>> {
+        Python::attach(|py| {
+            let value = self.0.call_method1(py, "get_user_option", (key,))?;
+            Ok(if value.is_none(py) { None } else { Some(value) })
+        })
+    }
+}
+
+impl TypedConfig for ConfigStack {
+    fn get_raw(&self, key: &str) -> Result<Option<Py<PyAny>>> {
+        self.get(key)
+    }
 }
 
 /// Get the global configuration stack.
@@ -226,6 +861,66 @@ pub fn global_stack() -> Result<ConfigStack> {
     })
 }
 
+/// Get the configuration stack for a location.
+///
+/// Options are looked up in the matching sections of `locations.conf` first,
+/// then in `breezy.conf`. Values set through the stack are written to the
+/// section for `location` in `locations.conf`.
+///
+/// `file://` URLs are converted to local paths first, so the section for
+/// `file:///srv/repos` is `/srv/repos`; use that id when accessing it through
+/// [`location_store`].
+///
+/// # Parameters
+///
+/// * `location` - The URL to get the configuration for.
+pub fn location_stack(location: &url::Url) -> Result<ConfigStack> {
+    Python::attach(|py| -> Result<ConfigStack> {
+        let m = py.import("breezy.config")?;
+        let stack = m.call_method1("LocationStack", (location.to_string(),))?;
+        Ok(ConfigStack::new(stack.unbind()))
+    })
+}
+
+/// Get the configuration stack containing only the options stored in a branch.
+///
+/// Unlike [`crate::branch::Branch::get_config_stack`], this does not fall back
+/// to `locations.conf` or `breezy.conf`.
+pub fn branch_only_stack(branch: &dyn crate::branch::PyBranch) -> Result<ConfigStack> {
+    Python::attach(|py| -> Result<ConfigStack> {
+        let m = py.import("breezy.config")?;
+        let stack = m.call_method1("BranchOnlyStack", (branch.to_object(py),))?;
+        Ok(ConfigStack::new(stack.unbind()))
+    })
+}
+
+/// Get the configuration stack for a remote control directory.
+pub fn remote_control_stack(
+    controldir: &dyn crate::controldir::PyControlDir,
+) -> Result<ConfigStack> {
+    Python::attach(|py| -> Result<ConfigStack> {
+        let m = py.import("breezy.config")?;
+        let stack = m.call_method1("RemoteControlStack", (controldir.to_object(py),))?;
+        Ok(ConfigStack::new(stack.unbind()))
+    })
+}
+
+/// Get the store for the global configuration file, `breezy.conf`.
+pub fn global_store() -> Result<ConfigStore> {
+    Python::attach(|py| -> Result<ConfigStore> {
+        let m = py.import("breezy.config")?;
+        Ok(ConfigStore(m.call_method0("GlobalStore")?.unbind()))
+    })
+}
+
+/// Get the store for the per-location configuration file, `locations.conf`.
+pub fn location_store() -> Result<ConfigStore> {
+    Python::attach(|py| -> Result<ConfigStore> {
+        let m = py.import("breezy.config")?;
+        Ok(ConfigStore(m.call_method0("LocationStore")?.unbind()))
+    })
+}
+
 /// Credentials for accessing a remote service.
 ///
 /// This struct contains the credentials for accessing a remote service,
@@ -541,6 +1236,115 @@ mod tests {
         std::mem::drop(env);
     }
 
+    #[test]
+    #[serial]
+    fn test_config_stack_typed() {
+        let env = crate::testing::TestEnv::new();
+        let stack = global_stack().unwrap();
+        stack.set("example.flag", "yes").unwrap();
+        stack.set("example.size", "10K").unwrap();
+        stack.set("example.items", "a, b,c").unwrap();
+        assert_eq!(stack.get_bool("example.flag").unwrap(), Some(true));
+        assert_eq!(stack.get_int("example.size").unwrap(), Some(10_000));
+        assert_eq!(
+            stack.get_list("example.items").unwrap(),
+            Some(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+        assert_eq!(stack.get_bool("example.unset").unwrap(), None);
+        assert!(matches!(
+            stack.get_int("example.flag"),
+            Err(Error::ConfigOptionValueError(_, _))
+        ));
+
+        let mut registry = OptionRegistry::new();
+        registry.register(
+            OptionDefinition::new("example.timeout", OptionType::Duration, "A timeout.")
+                .with_default("5m"),
+        );
+        registry.validate(&stack).unwrap();
+        assert_eq!(
+            stack
+                .get_option(registry.get("example.timeout").unwrap())
+                .unwrap(),
+            Some(OptionValue::Duration(chrono::Duration::minutes(5)))
+        );
+        registry.register(OptionDefinition::new(
+            "example.flag",
+            OptionType::Int,
+            "Not really an integer.",
+        ));
+        assert!(registry.validate(&stack).is_err());
+        std::mem::drop(env);
+    }
+
+    #[test]
+    #[serial]
+    fn test_location_stack() {
+        let env = crate::testing::TestEnv::new();
+        let parent: url::Url = "file:///srv/repos".parse().unwrap();
+        let child: url::Url = "file:///srv/repos/project".parse().unwrap();
+
+        location_stack(&parent)
+            .unwrap()
+            .set_with_policy(
+                "push_location",
+                "sftp://example.com/",
+                SectionPolicy::AppendPath,
+            )
+            .unwrap();
+        assert_eq!(
+            location_stack(&child)
+                .unwrap()
+                .get_string("push_location")
+                .unwrap(),
+            Some("sftp://example.com/project".to_string())
+        );
+
+        let store = location_store().unwrap();
+        assert!(store
+            .section_ids()
+            .unwrap()
+            .contains(&Some("/srv/repos".to_string())));
+        store
+            .set_policy(
+                Some("/srv/repos"),
+                "push_location",
+                SectionPolicy::NoRecurse,
+            )
+            .unwrap();
+        assert_eq!(
+            location_stack(&child)
+                .unwrap()
+                .get_string("push_location")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            store
+                .get(Some("/srv/repos"), "push_location:policy")
+                .unwrap(),
+            Some("norecurse".to_string())
+        );
+        std::mem::drop(env);
+    }
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(parse_bool("On"), Some(true));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+        assert_eq!(parse_int("42"), Some(42));
+        assert_eq!(parse_int("3MB"), Some(3_000_000));
+        assert_eq!(parse_int("x"), None);
+        assert_eq!(
+            parse_list("a, \"b\" ,,c"),
+            vec!["a".to_string(), "b".to_string(), "c".to_string()]
+        );
+        assert_eq!(parse_duration("90"), Some(chrono::Duration::seconds(90)));
+        assert_eq!(parse_duration("2h"), Some(chrono::Duration::hours(2)));
+        assert_eq!(parse_duration("soon"), None);
+    }
+
     #[test]
     fn test_parse_username() {
         assert_eq!(
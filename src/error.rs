@@ -100,6 +100,16 @@ import_exception!(breezy.errors, RedirectRequested);
 import_exception!(breezy.errors, ConflictsInTree);
 import_exception!(breezy.errors, NoRoundtrippingSupport);
 import_exception!(breezy.inter, NoCompatibleInter);
+import_exception!(breezy.shelf, NoSuchShelfId);
+import_exception!(breezy.config, ConfigOptionValueError);
+import_exception!(breezy.revisionspec, InvalidRevisionSpec);
+
+pyo3::create_exception!(
+    breezyshim,
+    CommitVetoed,
+    pyo3::exceptions::PyException,
+    "A pre-commit hook vetoed the commit."
+);
 
 lazy_static::lazy_static! {
     /// Static reference to the BreezyConnectionError class, if it exists.
@@ -237,6 +247,8 @@ pub enum Error {
     IncompatibleFormat(String, String),
     /// The specified revision does not exist.
     NoSuchRevision(crate::RevisionId),
+    /// The specified dotted revision number does not exist.
+    NoSuchRevno(Vec<u32>),
     /// The specified revision is not present in the repository.
     RevisionNotPresent(crate::RevisionId),
     /// The specified project does not exist.
@@ -270,6 +282,16 @@ pub enum Error {
     ReadOnly,
     /// An object that needs to be locked is not locked.
     ObjectNotLocked(String),
+    /// No changes are shelved with the specified id.
+    NoSuchShelfId(u32),
+    /// A configuration option has an invalid value.
+    ConfigOptionValueError(String, String),
+    /// A revision specifier is invalid or does not match a revision.
+    InvalidRevisionSpec(String, Option<String>),
+    /// The operation was cancelled before it completed.
+    Cancelled,
+    /// A pre-commit hook vetoed the commit.
+    CommitVetoed(String),
 }
 
 impl From<url::ParseError> for Error {
@@ -403,6 +425,7 @@ impl std::fmt::Display for Error {
                 write!(f, "Incompatible format: {} is not compatible with {}", a, b)
             }
             Self::NoSuchRevision(rev) => write!(f, "No such revision: {}", rev),
+            Self::NoSuchRevno(revno) => write!(f, "No such revision: {}", dotted_revno(revno)),
             Self::RevisionNotPresent(rev) => write!(f, "Revision not present: {}", rev),
             Self::NoSuchProject(p) => write!(f, "No such project: {}", p),
             Self::ForkingDisabled(p) => write!(f, "Forking disabled: {}", p),
@@ -417,6 +440,19 @@ impl std::fmt::Display for Error {
             Self::NoCompatibleInter => write!(f, "No compatible inter"),
             Self::ReadOnly => write!(f, "Read-only"),
             Self::ObjectNotLocked(msg) => write!(f, "Object not locked: {}", msg),
+            Self::NoSuchShelfId(id) => write!(f, "No changes are shelved with id {}", id),
+            Self::ConfigOptionValueError(name, value) => {
+                write!(f, "Bad value \"{}\" for option \"{}\"", value, name)
+            }
+            Self::Cancelled => write!(f, "Operation was cancelled"),
+            Self::CommitVetoed(reason) => write!(f, "Commit vetoed: {}", reason),
+            Self::InvalidRevisionSpec(spec, extra) => {
+                write!(f, "Invalid revision specifier: {}", spec)?;
+                if let Some(extra) = extra {
+                    write!(f, ": {}", extra)?;
+                }
+                Ok(())
+            }
             Self::RedirectRequested {
                 source,
                 target,
@@ -434,6 +470,15 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+/// Format a dotted revision number, e.g. `[2, 1, 3]` as `2.1.3`.
+fn dotted_revno(revno: &[u32]) -> String {
+    revno
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
 /// Convert a Python headers object (dict or HTTPMessage) to a HashMap<String, String>
 fn extract_headers(headers_obj: &Bound<PyAny>) -> std::collections::HashMap<String, String> {
     // Try to extract as a dict first
@@ -774,6 +819,33 @@ impl From<PyErr> for Error {
                 Error::BranchReferenceLoop
             } else if err.is_instance_of::<ObjectNotLocked>(py) {
                 Error::ObjectNotLocked(err.to_string())
+            } else if err.is_instance_of::<NoSuchShelfId>(py) {
+                Error::NoSuchShelfId(value.getattr("shelf_id").unwrap().extract().unwrap())
+            } else if err.is_instance_of::<ConfigOptionValueError>(py) {
+                Error::ConfigOptionValueError(
+                    value.getattr("name").unwrap().extract().unwrap(),
+                    value.getattr("value").unwrap().str().unwrap().to_string(),
+                )
+            } else if err.is_instance_of::<InvalidRevisionSpec>(py) {
+                let extra: Option<String> = value.getattr("extra").unwrap().extract().unwrap();
+                Error::InvalidRevisionSpec(
+                    value.getattr("spec").unwrap().str().unwrap().to_string(),
+                    extra
+                        .map(|e| e.trim().to_string())
+                        .filter(|e| !e.is_empty()),
+                )
+            } else if err.is_instance_of::<pyo3::exceptions::asyncio::CancelledError>(py) {
+                Error::Cancelled
+            } else if err.is_instance_of::<CommitVetoed>(py) {
+                Error::CommitVetoed(
+                    value
+                        .getattr("args")
+                        .unwrap()
+                        .get_item(0)
+                        .unwrap()
+                        .extract()
+                        .unwrap(),
+                )
             } else {
                 if std::env::var("BRZ_ERROR").is_ok() {
                     // Print backtrace
@@ -843,9 +915,19 @@ impl From<Error> for PyErr {
             Error::MergeProposalExists(source, _target) => {
                 Python::attach(|py| MergeProposalExists::new_err((source.to_string(), py.None())))
             }
-            Error::UnsupportedOperation(mname, tname) => {
-                UnsupportedOperation::new_err((mname, tname))
-            }
+            Error::UnsupportedOperation(mname, tname) => Python::attach(|py| {
+                // UnsupportedOperation.__init__ takes the method and object
+                // themselves, so set the attributes it would derive directly.
+                let cls = py.get_type::<UnsupportedOperation>();
+                match cls.call_method1("__new__", (&cls,)).and_then(|e| {
+                    e.setattr("mname", mname)?;
+                    e.setattr("tname", tname)?;
+                    Ok(e)
+                }) {
+                    Ok(e) => PyErr::from_value(e),
+                    Err(e) => e,
+                }
+            }),
             Error::ProtectedBranchHookDeclined(msg) => ProtectedBranchHookDeclined::new_err((msg,)),
             Error::NoRepositoryPresent => {
                 Python::attach(|py| NoRepositoryPresent::new_err((py.None(),)))
@@ -893,6 +975,9 @@ impl From<Error> for PyErr {
             Error::NoSuchRevision(rev) => {
                 Python::attach(|py| NoSuchRevision::new_err((py.None(), rev.to_string())))
             }
+            Error::NoSuchRevno(revno) => {
+                Python::attach(|py| NoSuchRevision::new_err((py.None(), dotted_revno(&revno))))
+            }
             Error::RevisionNotPresent(rev) => RevisionNotPresent::new_err((rev.to_string(),)),
             Error::NoSuchProject(p) => NoSuchProject::new_err((p,)),
             Error::ForkingDisabled(p) => ForkingDisabled::new_err((p,)),
@@ -903,6 +988,15 @@ impl From<Error> for PyErr {
             Error::BranchReferenceLoop => BranchReferenceLoop::new_err(()),
             Error::ReadOnly => Python::attach(|py| ReadOnlyError::new_err((py.None(),))),
             Error::ObjectNotLocked(msg) => ObjectNotLocked::new_err((msg,)),
+            Error::NoSuchShelfId(id) => NoSuchShelfId::new_err((id,)),
+            Error::ConfigOptionValueError(name, value) => {
+                ConfigOptionValueError::new_err((name, value))
+            }
+            Error::Cancelled => pyo3::exceptions::asyncio::CancelledError::new_err(()),
+            Error::CommitVetoed(reason) => CommitVetoed::new_err((reason,)),
+            Error::InvalidRevisionSpec(spec, extra) => {
+                Python::attach(|py| InvalidRevisionSpec::new_err((spec, py.None(), extra)))
+            }
             Error::RedirectRequested {
                 source,
                 target,
@@ -1245,7 +1339,6 @@ fn test_error_merge_proposal_exists() {
 }
 
 #[test]
-#[ignore] // UnsupportedOperation takes two arguments, which is not implemented
 fn test_error_unsupported_operation() {
     let e = Error::UnsupportedOperation("foo".to_string(), "bar".to_string());
     let p: PyErr = e.into();
@@ -1253,6 +1346,10 @@ fn test_error_unsupported_operation() {
     Python::attach(|py| {
         assert!(p.is_instance_of::<UnsupportedOperation>(py), "{}", p);
     });
+    assert!(matches!(
+        Error::from(p),
+        Error::UnsupportedOperation(m, t) if m == "foo" && t == "bar"
+    ));
 }
 
 #[test]
@@ -1571,3 +1668,58 @@ fn test_redirect_requested() {
         assert!(p.is_instance_of::<RedirectRequested>(py), "{}", p);
     });
 }
+
+#[test]
+fn test_no_such_shelf_id() {
+    let e = Error::NoSuchShelfId(3);
+    let p: PyErr = e.into();
+    // Verify that p is an instance of NoSuchShelfId
+    Python::attach(|py| {
+        assert!(p.is_instance_of::<NoSuchShelfId>(py), "{}", p);
+    });
+}
+
+#[test]
+fn test_config_option_value_error() {
+    let e = Error::ConfigOptionValueError("foo".to_string(), "bar".to_string());
+    let p: PyErr = e.into();
+    // Verify that p is an instance of ConfigOptionValueError
+    Python::attach(|py| {
+        assert!(p.is_instance_of::<ConfigOptionValueError>(py), "{}", p);
+    });
+}
+
+#[test]
+fn test_invalid_revision_spec() {
+    let e = Error::InvalidRevisionSpec("revno:42".to_string(), None);
+    let p: PyErr = e.into();
+    // Verify that p is an instance of InvalidRevisionSpec
+    Python::attach(|py| {
+        assert!(p.is_instance_of::<InvalidRevisionSpec>(py), "{}", p);
+    });
+}
+
+#[test]
+fn test_cancelled() {
+    let e = Error::Cancelled;
+    let p: PyErr = e.into();
+    // Verify that p is an instance of CancelledError
+    Python::attach(|py| {
+        assert!(
+            p.is_instance_of::<pyo3::exceptions::asyncio::CancelledError>(py),
+            "{}",
+            p
+        );
+    });
+}
+
+#[test]
+fn test_commit_vetoed() {
+    let e = Error::CommitVetoed("no binaries".to_string());
+    let p: PyErr = e.into();
+    // Verify that p is an instance of CommitVetoed
+    Python::attach(|py| {
+        assert!(p.is_instance_of::<CommitVetoed>(py), "{}", p);
+    });
+    assert!(matches!(Error::from(p), Error::CommitVetoed(reason) if reason == "no binaries"));
+}
@@ -2,10 +2,15 @@
 use crate::branch::{py_tag_selector, Branch, GenericBranch, PyBranch};
 use crate::error::Error;
 use crate::revisionid::RevisionId;
+use crate::transport::{add_classmethod, add_method, MethodArgs};
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 use std::hash::Hash;
+use std::sync::{Arc, OnceLock};
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 /// Represents a code forge (hosting service) like GitHub, GitLab, etc.
 pub struct Forge(Py<PyAny>);
@@ -129,6 +134,22 @@ impl From<Py<PyAny>> for MergeProposal {
 }
 
 impl MergeProposal {
+    /// Wrap a merge proposal implemented in Rust.
+    pub fn from_backend(backend: Box<dyn ProposalBackend>) -> Result<Self, Error> {
+        Python::attach(|py| Ok(MergeProposal(wrap_proposal(py, Arc::from(backend))?)))
+    }
+
+    /// Returns the Rust implementation of the merge proposal, if it has one.
+    ///
+    /// Returns `None` for merge proposals implemented in Python.
+    pub fn backend(&self) -> Option<Arc<dyn ProposalBackend>> {
+        Python::attach(|py| {
+            let handle = self.0.bind(py).getattr("_backend").ok()?;
+            let handle = handle.extract::<PyRef<ProposalHandle>>().ok()?;
+            Some(handle.0.clone())
+        })
+    }
+
     /// Create a merge proposal reference from a URL.
     pub fn from_url(url: &url::Url) -> Result<Self, Error> {
         get_proposal_by_url(url)
@@ -319,7 +340,11 @@ impl MergeProposal {
     /// The `auto` parameter determines whether to use automatic merging.
     pub fn merge(&self, auto: bool) -> Result<(), Error> {
         Python::attach(|py| {
-            self.0.call_method1(py, "merge", (auto,))?;
+            // The first positional argument of MergeProposal.merge() is the
+            // commit message, so `auto` has to be passed by keyword.
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("auto", auto)?;
+            self.0.call_method(py, "merge", (), Some(&kwargs))?;
             Ok(())
         })
     }
@@ -364,6 +389,19 @@ impl MergeProposal {
     }
 }
 
+/// Lazily converts the items yielded by a Python iterator.
+///
+/// Errors raised by `__next__` are yielded as items.
+fn py_iter<T: From<Py<PyAny>>>(iter: Py<PyAny>) -> impl Iterator<Item = Result<T, Error>> {
+    std::iter::from_fn(move || {
+        Python::attach(|py| match iter.call_method0(py, "__next__") {
+            Ok(item) => Some(Ok(T::from(item))),
+            Err(e) if e.is_instance_of::<pyo3::exceptions::PyStopIteration>(py) => None,
+            Err(e) => Some(Err(e.into())),
+        })
+    })
+}
+
 #[pyclass]
 /// Builder for creating merge proposals.
 pub struct ProposalBuilder(Py<PyAny>, Py<PyDict>);
@@ -449,6 +487,80 @@ impl ProposalBuilder {
     }
 }
 
+/// Filters for [`Forge::iter_proposals_matching`] and
+/// [`Forge::iter_my_proposals_matching`].
+///
+/// Filters the forge can't apply itself are applied to each proposal as it
+/// is retrieved, so with a [`ProposalQuery::limit`] only as many proposals
+/// are fetched as needed.
+#[derive(Debug, Clone, Default)]
+pub struct ProposalQuery {
+    status: Option<MergeProposalStatus>,
+    author: Option<String>,
+    target_branch: Option<url::Url>,
+    limit: Option<usize>,
+}
+
+impl ProposalQuery {
+    /// Creates a query that matches all proposals the forge returns by default.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only matches proposals with the given status.
+    pub fn status(mut self, status: MergeProposalStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Only matches proposals created by the given user.
+    ///
+    /// Breezy only lets forges filter by author when listing a user's
+    /// proposals, so only [`Forge::iter_my_proposals_matching`] supports this.
+    pub fn author(mut self, author: &str) -> Self {
+        self.author = Some(author.to_string());
+        self
+    }
+
+    /// Only matches proposals that target the branch at the given URL.
+    pub fn target_branch(mut self, url: &url::Url) -> Self {
+        self.target_branch = Some(url.clone());
+        self
+    }
+
+    /// Returns at most `limit` proposals.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Checks whether a proposal matches the filters that can't be passed
+    /// on to the forge.
+    fn matches(&self, proposal: &MergeProposal) -> Result<bool, Error> {
+        if let Some(target_branch) = self.target_branch.as_ref() {
+            let url = proposal.get_target_branch_url()?;
+            if url.as_ref().map(|u| u.as_str().trim_end_matches('/'))
+                != Some(target_branch.as_str().trim_end_matches('/'))
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Lazily filters the proposals yielded by a Python iterator.
+    fn filter(self, iter: Py<PyAny>) -> impl Iterator<Item = Result<MergeProposal, Error>> {
+        let limit = self.limit.unwrap_or(usize::MAX);
+        py_iter(iter)
+            .filter_map(move |proposal: Result<MergeProposal, Error>| {
+                proposal
+                    .and_then(|p| Ok(self.matches(&p)?.then_some(p)))
+                    .transpose()
+            })
+            .take(limit)
+    }
+}
+
 impl Forge {
     fn to_object(&self) -> &Py<PyAny> {
         &self.0
@@ -594,13 +706,7 @@ impl Forge {
                 .into_any()
                 .unbind())
         })?;
-        Ok(std::iter::from_fn(move || {
-            Python::attach(|py| match iter.call_method0(py, "__next__") {
-                Ok(proposal) => Some(Ok(MergeProposal::from(proposal))),
-                Err(e) if e.is_instance_of::<pyo3::exceptions::PyStopIteration>(py) => None,
-                Err(e) => Some(Err(e.into())),
-            })
-        }))
+        Ok(py_iter(iter))
     }
 
     /// Gets a branch derived from a main branch with the given name and optional owner.
@@ -687,6 +793,70 @@ impl Forge {
         })
     }
 
+    /// Returns a lazy iterator over the merge proposals from one branch to
+    /// another that match a query.
+    ///
+    /// The status is passed on to the forge; the other filters are applied
+    /// to each proposal as it is retrieved. Returns
+    /// [`Error::UnsupportedOperation`] if the query filters by author.
+    pub fn iter_proposals_matching(
+        &self,
+        source_branch: &dyn PyBranch,
+        target_branch: &dyn PyBranch,
+        query: ProposalQuery,
+    ) -> Result<impl Iterator<Item = Result<MergeProposal, Error>>, Error> {
+        if query.author.is_some() {
+            return Err(Error::UnsupportedOperation(
+                "iter_proposals".to_string(),
+                self.forge_kind(),
+            ));
+        }
+        let iter = Python::attach(|py| -> Result<Py<PyAny>, Error> {
+            let kwargs = PyDict::new(py);
+            if let Some(status) = query.status {
+                kwargs.set_item("status", status)?;
+            }
+            Ok(self
+                .0
+                .call_method(
+                    py,
+                    "iter_proposals",
+                    (source_branch.to_object(py), target_branch.to_object(py)),
+                    Some(&kwargs),
+                )?
+                .bind(py)
+                .try_iter()?
+                .into_any()
+                .unbind())
+        })?;
+        Ok(query.filter(iter))
+    }
+
+    /// Returns a lazy iterator over the merge proposals owned by the current
+    /// user that match a query.
+    ///
+    /// The status and author are passed on to the forge; the other filters
+    /// are applied to each proposal as it is retrieved.
+    pub fn iter_my_proposals_matching(
+        &self,
+        query: ProposalQuery,
+    ) -> Result<impl Iterator<Item = Result<MergeProposal, Error>>, Error> {
+        let iter = Python::attach(|py| -> Result<Py<PyAny>, Error> {
+            Ok(self
+                .0
+                .call_method1(
+                    py,
+                    "iter_my_proposals",
+                    (query.status, query.author.as_deref()),
+                )?
+                .bind(py)
+                .try_iter()?
+                .into_any()
+                .unbind())
+        })?;
+        Ok(query.filter(iter))
+    }
+
     /// Publishes a derived branch and returns the branch and its URL.
     pub fn publish_derived_as_generic_branch(
         &self,
@@ -914,6 +1084,668 @@ pub fn get_proposal_by_url(url: &url::Url) -> Result<MergeProposal, Error> {
     })
 }
 
+/// The properties of a new merge proposal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProposalParams {
+    /// The description of the merge proposal.
+    pub description: Option<String>,
+    /// The title of the merge proposal.
+    pub title: Option<String>,
+    /// The commit message to use when merging.
+    pub commit_message: Option<String>,
+    /// Labels to add to the merge proposal.
+    pub labels: Vec<String>,
+    /// Users to request reviews from.
+    pub reviewers: Vec<String>,
+    /// Whether the merge proposal is a work in progress.
+    pub work_in_progress: bool,
+    /// Whether to allow the target branch owners to push to the source branch.
+    pub allow_collaboration: bool,
+}
+
+fn unsupported<T: ?Sized>(method: &str) -> Error {
+    Error::UnsupportedOperation(method.to_string(), std::any::type_name::<T>().to_string())
+}
+
+/// A merge proposal implemented in Rust.
+///
+/// Use [`MergeProposal::from_backend`] to wrap one in a [`MergeProposal`].
+/// Optional operations return [`Error::UnsupportedOperation`] by default.
+pub trait ProposalBackend: Send + Sync {
+    /// The URL of the merge proposal.
+    fn url(&self) -> url::Url;
+
+    /// The URL to view the merge proposal in a browser.
+    fn get_web_url(&self) -> Result<url::Url, Error> {
+        Ok(self.url())
+    }
+
+    /// The title of the merge proposal.
+    fn get_title(&self) -> Result<Option<String>, Error>;
+
+    /// Change the title of the merge proposal.
+    fn set_title(&self, _title: Option<&str>) -> Result<(), Error> {
+        Err(unsupported::<Self>("set_title"))
+    }
+
+    /// The description of the merge proposal.
+    fn get_description(&self) -> Result<Option<String>, Error>;
+
+    /// Change the description of the merge proposal.
+    fn set_description(&self, _description: Option<&str>) -> Result<(), Error> {
+        Err(unsupported::<Self>("set_description"))
+    }
+
+    /// The commit message to use when merging.
+    fn get_commit_message(&self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    /// Change the commit message to use when merging.
+    fn set_commit_message(&self, _commit_message: Option<&str>) -> Result<(), Error> {
+        Err(unsupported::<Self>("set_commit_message"))
+    }
+
+    /// The URL of the branch to merge.
+    fn get_source_branch_url(&self) -> Result<Option<url::Url>, Error>;
+
+    /// The URL of the branch to merge into.
+    fn get_target_branch_url(&self) -> Result<Option<url::Url>, Error>;
+
+    /// The revision to merge.
+    fn get_source_revision(&self) -> Result<Option<RevisionId>, Error>;
+
+    /// The status of the merge proposal; never [`MergeProposalStatus::All`].
+    fn status(&self) -> Result<MergeProposalStatus, Error>;
+
+    /// Close the merge proposal without merging it.
+    fn close(&self) -> Result<(), Error>;
+
+    /// Reopen a closed merge proposal.
+    fn reopen(&self) -> Result<(), Error>;
+
+    /// Check whether the merge proposal can be merged.
+    fn can_be_merged(&self) -> Result<bool, Error>;
+
+    /// Merge the merge proposal.
+    ///
+    /// # Arguments
+    ///
+    /// * `commit_message` - The commit message to use, if any
+    /// * `auto` - Whether to merge automatically once checks pass
+    fn merge(&self, commit_message: Option<&str>, auto: bool) -> Result<(), Error>;
+
+    /// The user who merged the merge proposal.
+    fn get_merged_by(&self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    /// When the merge proposal was merged.
+    fn get_merged_at(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>, Error> {
+        Ok(None)
+    }
+
+    /// Post a comment on the merge proposal.
+    fn post_comment(&self, _body: &str) -> Result<(), Error> {
+        Err(unsupported::<Self>("post_comment"))
+    }
+}
+
+/// A code forge implemented in Rust.
+///
+/// Register one with [`register_forge`] to make it available to
+/// [`get_forge`], [`get_forge_by_hostname`] and [`iter_forge_instances`].
+pub trait ForgeBackend: Send + Sync {
+    /// The base URL of the forge.
+    fn base_url(&self) -> url::Url;
+
+    /// Check whether a branch or merge proposal URL is hosted on this forge.
+    fn hosts(&self, url: &url::Url) -> bool;
+
+    /// Check whether this forge runs on a host.
+    fn hosts_hostname(&self, hostname: &str) -> bool {
+        self.base_url().host_str() == Some(hostname)
+    }
+
+    /// Whether merge proposals can have labels.
+    fn supports_merge_proposal_labels(&self) -> bool {
+        false
+    }
+
+    /// Whether merge proposals can have a title.
+    fn supports_merge_proposal_title(&self) -> bool {
+        true
+    }
+
+    /// Whether merge proposals can have a commit message.
+    fn supports_merge_proposal_commit_message(&self) -> bool {
+        false
+    }
+
+    /// The format of merge proposal descriptions, e.g. `"markdown"`.
+    fn merge_proposal_description_format(&self) -> String {
+        "plain".to_string()
+    }
+
+    /// The URL to view a branch in a browser.
+    fn get_web_url(&self, branch: &GenericBranch) -> Result<url::Url, Error> {
+        Ok(branch.get_user_url())
+    }
+
+    /// The URL to push to a branch.
+    fn get_push_url(&self, branch: &GenericBranch) -> Result<url::Url, Error> {
+        Ok(branch.get_user_url())
+    }
+
+    /// Publish a derived branch.
+    ///
+    /// # Arguments
+    ///
+    /// * `local_branch` - The branch to publish
+    /// * `base_branch` - The branch it is derived from
+    /// * `name` - The name of the derived branch
+    /// * `owner` - The owner of the derived branch, or `None` for the current user
+    /// * `revision_id` - The revision to publish, or `None` for the tip
+    /// * `overwrite` - Whether to overwrite the derived branch if it has diverged
+    /// * `tag_selector` - Selects the tags to publish, or `None` for all tags
+    ///
+    /// # Returns
+    ///
+    /// The published branch and its public URL
+    #[allow(clippy::too_many_arguments)]
+    fn publish_derived(
+        &self,
+        local_branch: &GenericBranch,
+        base_branch: &GenericBranch,
+        name: &str,
+        owner: Option<&str>,
+        revision_id: Option<&RevisionId>,
+        overwrite: bool,
+        tag_selector: Option<Box<dyn Fn(String) -> bool>>,
+    ) -> Result<(GenericBranch, url::Url), Error>;
+
+    /// Open a branch published with [`ForgeBackend::publish_derived`].
+    fn get_derived_branch(
+        &self,
+        base_branch: &GenericBranch,
+        name: &str,
+        owner: Option<&str>,
+    ) -> Result<GenericBranch, Error>;
+
+    /// Create a merge proposal.
+    ///
+    /// Returns [`Error::MergeProposalExists`] if there already is an open
+    /// merge proposal for the branches.
+    fn create_proposal(
+        &self,
+        source_branch: &GenericBranch,
+        target_branch: &GenericBranch,
+        params: ProposalParams,
+    ) -> Result<Box<dyn ProposalBackend>, Error>;
+
+    /// Look up a merge proposal by URL.
+    fn get_proposal_by_url(&self, url: &url::Url) -> Result<Box<dyn ProposalBackend>, Error>;
+
+    /// List the merge proposals from one branch to another.
+    fn iter_proposals(
+        &self,
+        source_branch: &url::Url,
+        target_branch: &url::Url,
+        status: MergeProposalStatus,
+    ) -> Result<Vec<Box<dyn ProposalBackend>>, Error>;
+
+    /// List the merge proposals created by a user, or by the current user.
+    fn iter_my_proposals(
+        &self,
+        status: MergeProposalStatus,
+        author: Option<&str>,
+    ) -> Result<Vec<Box<dyn ProposalBackend>>, Error>;
+
+    /// The name of the current user, if logged in.
+    fn get_current_user(&self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    /// The URL of the profile of a user.
+    fn get_user_url(&self, _user: &str) -> Result<url::Url, Error> {
+        Err(unsupported::<Self>("get_user_url"))
+    }
+}
+
+/// Holds the Rust implementation of a merge proposal on its Python object.
+#[pyclass(frozen)]
+struct ProposalHandle(Arc<dyn ProposalBackend>);
+
+static PROPOSAL_CLASS: OnceLock<Py<PyAny>> = OnceLock::new();
+
+fn proposal_backend(args: &MethodArgs) -> PyResult<Arc<dyn ProposalBackend>> {
+    let handle = args.this()?.getattr("_backend")?;
+    let handle = handle.extract::<PyRef<ProposalHandle>>()?;
+    Ok(handle.0.clone())
+}
+
+fn optional_string(args: &MethodArgs, index: usize, name: &str) -> PyResult<Option<String>> {
+    args.get(index, name)?.map(|v| v.extract()).transpose()
+}
+
+fn extract_url(value: &Bound<PyAny>) -> PyResult<url::Url> {
+    value
+        .extract::<String>()?
+        .parse()
+        .map_err(|e: url::ParseError| PyValueError::new_err(e.to_string()))
+}
+
+fn proposal_class(py: Python) -> PyResult<&'static Py<PyAny>> {
+    if let Some(class) = PROPOSAL_CLASS.get() {
+        return Ok(class);
+    }
+    let base_class = py.import("breezy.forge")?.getattr("MergeProposal")?;
+    let namespace = PyDict::new(py);
+    namespace.set_item("supports_auto_merge", true)?;
+
+    add_method(&namespace, "__init__", |args| {
+        let this = args.this()?;
+        let handle = args.required(1, "backend")?;
+        let url = handle.extract::<PyRef<ProposalHandle>>()?.0.url();
+        this.setattr("_backend", handle)?;
+        this.setattr("url", url.to_string())
+    })?;
+
+    add_method(&namespace, "get_web_url", |args| {
+        Ok(proposal_backend(args)?.get_web_url()?.to_string())
+    })?;
+    add_method(&namespace, "get_title", |args| {
+        Ok(proposal_backend(args)?.get_title()?)
+    })?;
+    add_method(&namespace, "set_title", |args| {
+        let title = optional_string(args, 1, "title")?;
+        Ok(proposal_backend(args)?.set_title(title.as_deref())?)
+    })?;
+    add_method(&namespace, "get_description", |args| {
+        Ok(proposal_backend(args)?.get_description()?)
+    })?;
+    add_method(&namespace, "set_description", |args| {
+        let description = optional_string(args, 1, "description")?;
+        Ok(proposal_backend(args)?.set_description(description.as_deref())?)
+    })?;
+    add_method(&namespace, "get_commit_message", |args| {
+        Ok(proposal_backend(args)?.get_commit_message()?)
+    })?;
+    add_method(&namespace, "set_commit_message", |args| {
+        let commit_message = optional_string(args, 1, "commit_message")?;
+        Ok(proposal_backend(args)?.set_commit_message(commit_message.as_deref())?)
+    })?;
+    add_method(&namespace, "get_source_branch_url", |args| {
+        Ok(proposal_backend(args)?
+            .get_source_branch_url()?
+            .map(|u| u.to_string()))
+    })?;
+    add_method(&namespace, "get_target_branch_url", |args| {
+        Ok(proposal_backend(args)?
+            .get_target_branch_url()?
+            .map(|u| u.to_string()))
+    })?;
+    add_method(&namespace, "get_source_revision", |args| {
+        Ok(proposal_backend(args)?.get_source_revision()?)
+    })?;
+    add_method(&namespace, "is_merged", |args| {
+        Ok(proposal_backend(args)?.status()? == MergeProposalStatus::Merged)
+    })?;
+    add_method(&namespace, "is_closed", |args| {
+        Ok(proposal_backend(args)?.status()? == MergeProposalStatus::Closed)
+    })?;
+    add_method(&namespace, "close", |args| {
+        Ok(proposal_backend(args)?.close()?)
+    })?;
+    add_method(&namespace, "reopen", |args| {
+        Ok(proposal_backend(args)?.reopen()?)
+    })?;
+    add_method(&namespace, "can_be_merged", |args| {
+        Ok(proposal_backend(args)?.can_be_merged()?)
+    })?;
+    add_method(&namespace, "merge", |args| {
+        let commit_message = optional_string(args, 1, "commit_message")?;
+        let auto = match args.get(2, "auto")? {
+            Some(auto) => auto.extract()?,
+            None => false,
+        };
+        Ok(proposal_backend(args)?.merge(commit_message.as_deref(), auto)?)
+    })?;
+    add_method(&namespace, "get_merged_by", |args| {
+        Ok(proposal_backend(args)?.get_merged_by()?)
+    })?;
+    add_method(&namespace, "get_merged_at", |args| {
+        Ok(proposal_backend(args)?.get_merged_at()?)
+    })?;
+    add_method(&namespace, "post_comment", |args| {
+        let body: String = args.required(1, "body")?.extract()?;
+        Ok(proposal_backend(args)?.post_comment(&body)?)
+    })?;
+
+    let class = py.import("builtins")?.getattr("type")?.call1((
+        "RustMergeProposal",
+        (base_class,),
+        namespace,
+    ))?;
+    Ok(PROPOSAL_CLASS.get_or_init(|| class.unbind()))
+}
+
+fn wrap_proposal(py: Python, backend: Arc<dyn ProposalBackend>) -> PyResult<Py<PyAny>> {
+    let handle = Bound::new(py, ProposalHandle(backend))?;
+    proposal_class(py)?.call1(py, (handle,))
+}
+
+fn wrap_proposals(
+    py: Python,
+    proposals: Vec<Box<dyn ProposalBackend>>,
+) -> PyResult<Vec<Py<PyAny>>> {
+    proposals
+        .into_iter()
+        .map(|p| wrap_proposal(py, Arc::from(p)))
+        .collect()
+}
+
+fn unsupported_forge<'py>(py: Python<'py>, location: impl IntoPyObject<'py>) -> PyErr {
+    match py
+        .import("breezy.forge")
+        .and_then(|m| m.getattr("UnsupportedForge")?.call1((location,)))
+    {
+        Ok(e) => PyErr::from_value(e),
+        Err(e) => e,
+    }
+}
+
+fn branch_arg(args: &MethodArgs, index: usize, name: &str) -> PyResult<GenericBranch> {
+    Ok(GenericBranch::from(args.required(index, name)?.unbind()))
+}
+
+fn status_arg(args: &MethodArgs, index: usize) -> PyResult<MergeProposalStatus> {
+    match args.get(index, "status")? {
+        Some(status) => status.extract(),
+        None => Ok(MergeProposalStatus::Open),
+    }
+}
+
+fn builder_class<'py>(
+    py: Python<'py>,
+    backend: Arc<dyn ForgeBackend>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let base_class = py.import("breezy.forge")?.getattr("MergeProposalBuilder")?;
+    let namespace = PyDict::new(py);
+
+    add_method(&namespace, "__init__", |args| {
+        let this = args.this()?;
+        this.setattr("source_branch", args.required(1, "source_branch")?)?;
+        this.setattr("target_branch", args.required(2, "target_branch")?)?;
+        Ok(())
+    })?;
+
+    add_method(&namespace, "get_initial_body", |args| Ok(args.py().None()))?;
+
+    add_method(&namespace, "get_infotext", |_args| Ok(""))?;
+
+    add_method(&namespace, "create_proposal", move |args| {
+        let this = args.this()?;
+        let strings = |index, name| -> PyResult<Vec<String>> {
+            Ok(args
+                .get(index, name)?
+                .map(|v| v.extract())
+                .transpose()?
+                .unwrap_or_default())
+        };
+        let flag = |index, name| -> PyResult<bool> {
+            Ok(args
+                .get(index, name)?
+                .map(|v| v.extract())
+                .transpose()?
+                .unwrap_or(false))
+        };
+        let params = ProposalParams {
+            description: optional_string(args, 1, "description")?,
+            title: optional_string(args, 2, "title")?,
+            reviewers: strings(3, "reviewers")?,
+            labels: strings(4, "labels")?,
+            commit_message: optional_string(args, 6, "commit_message")?,
+            work_in_progress: flag(7, "work_in_progress")?,
+            allow_collaboration: flag(8, "allow_collaboration")?,
+        };
+        let proposal = backend.create_proposal(
+            &GenericBranch::from(this.getattr("source_branch")?.unbind()),
+            &GenericBranch::from(this.getattr("target_branch")?.unbind()),
+            params,
+        )?;
+        wrap_proposal(args.py(), Arc::from(proposal))
+    })?;
+
+    py.import("builtins")?.getattr("type")?.call1((
+        "RustMergeProposalBuilder",
+        (base_class,),
+        namespace,
+    ))
+}
+
+fn forge_class<'py>(
+    py: Python<'py>,
+    name: &str,
+    backend: Arc<dyn ForgeBackend>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let base_class = py.import("breezy.forge")?.getattr("Forge")?;
+    let builder_class = builder_class(py, backend.clone())?.unbind();
+    let namespace = PyDict::new(py);
+    namespace.set_item(
+        "supports_merge_proposal_labels",
+        backend.supports_merge_proposal_labels(),
+    )?;
+    namespace.set_item(
+        "supports_merge_proposal_title",
+        backend.supports_merge_proposal_title(),
+    )?;
+    namespace.set_item(
+        "supports_merge_proposal_commit_message",
+        backend.supports_merge_proposal_commit_message(),
+    )?;
+    namespace.set_item(
+        "merge_proposal_description_format",
+        backend.merge_proposal_description_format(),
+    )?;
+
+    let b = backend.clone();
+    let fget = pyo3::types::PyCFunction::new_closure(
+        py,
+        None,
+        None,
+        move |_args: &Bound<pyo3::types::PyTuple>,
+              _kwargs: Option<&Bound<PyDict>>|
+              -> PyResult<String> { Ok(b.base_url().to_string()) },
+    )?;
+    namespace.set_item(
+        "base_url",
+        py.import("builtins")?.getattr("property")?.call1((fget,))?,
+    )?;
+
+    let b = backend.clone();
+    add_classmethod(&namespace, "probe_from_branch", move |args| {
+        let branch = args.required(1, "branch")?;
+        if !b.hosts(&extract_url(&branch.getattr("user_url")?)?) {
+            return Err(unsupported_forge(args.py(), branch));
+        }
+        Ok(args.this()?.call0()?.unbind())
+    })?;
+
+    let b = backend.clone();
+    add_classmethod(&namespace, "probe_from_url", move |args| {
+        let url = args.required(1, "url")?;
+        if !b.hosts(&extract_url(&url)?) {
+            return Err(unsupported_forge(args.py(), url));
+        }
+        Ok(args.this()?.call0()?.unbind())
+    })?;
+
+    let b = backend.clone();
+    add_classmethod(&namespace, "probe_from_hostname", move |args| {
+        let hostname = args.required(1, "hostname")?;
+        if !b.hosts_hostname(&hostname.extract::<String>()?) {
+            return Err(unsupported_forge(args.py(), hostname));
+        }
+        Ok(args.this()?.call0()?.unbind())
+    })?;
+
+    add_classmethod(&namespace, "iter_instances", |args| {
+        Ok(vec![args.this()?.call0()?.unbind()])
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "hosts", move |args| {
+        let branch = args.required(1, "branch")?;
+        Ok(b.hosts(&extract_url(&branch.getattr("user_url")?)?))
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "get_web_url", move |args| {
+        Ok(b.get_web_url(&branch_arg(args, 1, "branch")?)?.to_string())
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "get_push_url", move |args| {
+        Ok(b.get_push_url(&branch_arg(args, 1, "branch")?)?.to_string())
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "get_current_user", move |_args| {
+        Ok(b.get_current_user()?)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "get_user_url", move |args| {
+        let user: String = args.required(1, "user")?.extract()?;
+        Ok(b.get_user_url(&user)?.to_string())
+    })?;
+
+    add_method(&namespace, "get_proposer", move |args| {
+        builder_class.call1(
+            args.py(),
+            (
+                args.required(1, "source_branch")?,
+                args.required(2, "target_branch")?,
+            ),
+        )
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "get_proposal_by_url", move |args| {
+        let url = extract_url(&args.required(1, "url")?)?;
+        wrap_proposal(args.py(), Arc::from(b.get_proposal_by_url(&url)?))
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "iter_proposals", move |args| {
+        let source = branch_arg(args, 1, "source_branch")?;
+        let target = branch_arg(args, 2, "target_branch")?;
+        let proposals = b.iter_proposals(
+            &source.get_user_url(),
+            &target.get_user_url(),
+            status_arg(args, 3)?,
+        )?;
+        wrap_proposals(args.py(), proposals)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "iter_my_proposals", move |args| {
+        let author = optional_string(args, 2, "author")?;
+        let proposals = b.iter_my_proposals(status_arg(args, 1)?, author.as_deref())?;
+        wrap_proposals(args.py(), proposals)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "publish_derived", move |args| {
+        let py = args.py();
+        let name: String = args.required(3, "name")?.extract()?;
+        let owner = optional_string(args, 5, "owner")?;
+        let revision_id: Option<RevisionId> = args
+            .get(6, "revision_id")?
+            .map(|r| r.extract())
+            .transpose()?;
+        let overwrite = match args.get(7, "overwrite")? {
+            Some(overwrite) => overwrite.extract()?,
+            None => false,
+        };
+        let tag_selector = args.get(9, "tag_selector")?.map(|f| {
+            let f = f.unbind();
+            Box::new(move |tag: String| {
+                Python::attach(|py| {
+                    f.call1(py, (tag,))
+                        .and_then(|r| r.extract(py))
+                        .unwrap_or(false)
+                })
+            }) as Box<dyn Fn(String) -> bool>
+        });
+        let (branch, url) = b.publish_derived(
+            &branch_arg(args, 1, "local_branch")?,
+            &branch_arg(args, 2, "base_branch")?,
+            &name,
+            owner.as_deref(),
+            revision_id.as_ref(),
+            overwrite,
+            tag_selector,
+        )?;
+        Ok((branch.to_object(py), url.to_string()))
+    })?;
+
+    let b = backend;
+    add_method(&namespace, "get_derived_branch", move |args| {
+        let name: String = args.required(2, "name")?.extract()?;
+        let owner = optional_string(args, 4, "owner")?;
+        let branch = b.get_derived_branch(
+            &branch_arg(args, 1, "base_branch")?,
+            &name,
+            owner.as_deref(),
+        )?;
+        Ok(branch.to_object(args.py()))
+    })?;
+
+    py.import("builtins")?
+        .getattr("type")?
+        .call1((name, (base_class,), namespace))
+}
+
+/// Register a forge implemented in Rust.
+///
+/// After registration, [`get_forge`], [`get_forge_by_hostname`],
+/// [`iter_forge_instances`] and [`get_proposal_by_url`] consider the forge.
+/// Any forge previously registered under the same name is replaced.
+///
+/// # Arguments
+/// * `name` - The name to register the forge under; also its [`Forge::forge_kind`]
+/// * `backend` - The implementation of the forge
+///
+/// # Returns
+///
+/// The registered forge
+pub fn register_forge(name: &str, backend: Box<dyn ForgeBackend>) -> Result<Forge, Error> {
+    Python::attach(|py| {
+        let class = forge_class(py, name, Arc::from(backend))?;
+        let forges = py.import("breezy.forge")?.getattr("forges")?;
+        if forges.contains(name)? {
+            forges.call_method1("remove", (name,))?;
+        }
+        forges.call_method1("register", (name, &class))?;
+        Ok(Forge(class.call0()?.unbind()))
+    })
+}
+
+/// Unregister a forge registered with [`register_forge`].
+///
+/// Does nothing if no forge is registered under `name`.
+pub fn unregister_forge(name: &str) -> Result<(), Error> {
+    Python::attach(|py| {
+        let forges = py.import("breezy.forge")?.getattr("forges")?;
+        if forges.contains(name)? {
+            forges.call_method1("remove", (name,))?;
+        }
+        Ok(())
+    })
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -931,4 +1763,228 @@ mod tests {
             super::determine_title(description).unwrap_err()
         );
     }
+
+    fn eval(code: &str) -> pyo3::Py<pyo3::PyAny> {
+        use pyo3::prelude::*;
+        Python::attach(|py| {
+            py.eval(std::ffi::CString::new(code).unwrap().as_c_str(), None, None)
+                .unwrap()
+                .unbind()
+        })
+    }
+
+    fn mock_proposal(expr: &str) -> super::MergeProposal {
+        eval(&format!("__import__('types').SimpleNamespace({})", expr)).into()
+    }
+
+    #[test]
+    fn test_proposal_query() {
+        let forge: super::Forge = eval(concat!(
+            "(lambda mp: __import__('types').SimpleNamespace(",
+            "iter_my_proposals=lambda status, author: iter([",
+            "mp('https://example.com/1', 'https://example.com/trunk'), ",
+            "mp('https://example.com/2', 'https://example.com/other'), ",
+            "mp('https://example.com/3', 'https://example.com/trunk/'), ",
+            "mp('https://example.com/4', 'https://example.com/trunk'), ",
+            "])))(lambda url, target: __import__('types').SimpleNamespace(",
+            "url=url, get_target_branch_url=lambda: target))"
+        ))
+        .into();
+        let urls = |query| {
+            forge
+                .iter_my_proposals_matching(query)
+                .unwrap()
+                .map(|p| p.unwrap().url().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(urls(super::ProposalQuery::new()).len(), 4);
+        let trunk: url::Url = "https://example.com/trunk".parse().unwrap();
+        assert_eq!(
+            urls(super::ProposalQuery::new().target_branch(&trunk)),
+            vec![
+                "https://example.com/1",
+                "https://example.com/3",
+                "https://example.com/4"
+            ]
+        );
+        assert_eq!(
+            urls(super::ProposalQuery::new().target_branch(&trunk).limit(2)),
+            vec!["https://example.com/1", "https://example.com/3"]
+        );
+
+        let branch = crate::branch::GenericBranch::from(eval("None"));
+        assert!(matches!(
+            forge.iter_proposals_matching(
+                &branch,
+                &branch,
+                super::ProposalQuery::new().author("bob")
+            ),
+            Err(crate::error::Error::UnsupportedOperation(..))
+        ));
+    }
+
+    struct StaticProposal;
+
+    impl super::ProposalBackend for StaticProposal {
+        fn url(&self) -> url::Url {
+            "https://example.com/proposals/1".parse().unwrap()
+        }
+
+        fn get_title(&self) -> Result<Option<String>, crate::error::Error> {
+            Ok(Some("Fix it".to_string()))
+        }
+
+        fn get_description(&self) -> Result<Option<String>, crate::error::Error> {
+            Ok(None)
+        }
+
+        fn get_source_branch_url(&self) -> Result<Option<url::Url>, crate::error::Error> {
+            Ok(Some("https://example.com/feature".parse().unwrap()))
+        }
+
+        fn get_target_branch_url(&self) -> Result<Option<url::Url>, crate::error::Error> {
+            Ok(Some("https://example.com/trunk".parse().unwrap()))
+        }
+
+        fn get_source_revision(&self) -> Result<Option<crate::RevisionId>, crate::error::Error> {
+            Ok(None)
+        }
+
+        fn status(&self) -> Result<super::MergeProposalStatus, crate::error::Error> {
+            Ok(super::MergeProposalStatus::Merged)
+        }
+
+        fn close(&self) -> Result<(), crate::error::Error> {
+            Ok(())
+        }
+
+        fn reopen(&self) -> Result<(), crate::error::Error> {
+            Ok(())
+        }
+
+        fn can_be_merged(&self) -> Result<bool, crate::error::Error> {
+            Ok(false)
+        }
+
+        fn merge(
+            &self,
+            _commit_message: Option<&str>,
+            _auto: bool,
+        ) -> Result<(), crate::error::Error> {
+            Ok(())
+        }
+    }
+
+    struct StaticForge;
+
+    impl super::ForgeBackend for StaticForge {
+        fn base_url(&self) -> url::Url {
+            "https://rust-forge.invalid/".parse().unwrap()
+        }
+
+        fn hosts(&self, url: &url::Url) -> bool {
+            url.host_str() == Some("rust-forge.invalid")
+        }
+
+        fn publish_derived(
+            &self,
+            _local_branch: &crate::branch::GenericBranch,
+            _base_branch: &crate::branch::GenericBranch,
+            _name: &str,
+            _owner: Option<&str>,
+            _revision_id: Option<&crate::RevisionId>,
+            _overwrite: bool,
+            _tag_selector: Option<Box<dyn Fn(String) -> bool>>,
+        ) -> Result<(crate::branch::GenericBranch, url::Url), crate::error::Error> {
+            Err(super::unsupported::<Self>("publish_derived"))
+        }
+
+        fn get_derived_branch(
+            &self,
+            _base_branch: &crate::branch::GenericBranch,
+            _name: &str,
+            _owner: Option<&str>,
+        ) -> Result<crate::branch::GenericBranch, crate::error::Error> {
+            Err(super::unsupported::<Self>("get_derived_branch"))
+        }
+
+        fn create_proposal(
+            &self,
+            _source_branch: &crate::branch::GenericBranch,
+            _target_branch: &crate::branch::GenericBranch,
+            _params: super::ProposalParams,
+        ) -> Result<Box<dyn super::ProposalBackend>, crate::error::Error> {
+            Err(super::unsupported::<Self>("create_proposal"))
+        }
+
+        fn get_proposal_by_url(
+            &self,
+            _url: &url::Url,
+        ) -> Result<Box<dyn super::ProposalBackend>, crate::error::Error> {
+            Ok(Box::new(StaticProposal))
+        }
+
+        fn iter_proposals(
+            &self,
+            _source_branch: &url::Url,
+            _target_branch: &url::Url,
+            _status: super::MergeProposalStatus,
+        ) -> Result<Vec<Box<dyn super::ProposalBackend>>, crate::error::Error> {
+            Ok(vec![Box::new(StaticProposal)])
+        }
+
+        fn iter_my_proposals(
+            &self,
+            _status: super::MergeProposalStatus,
+            _author: Option<&str>,
+        ) -> Result<Vec<Box<dyn super::ProposalBackend>>, crate::error::Error> {
+            Ok(vec![Box::new(StaticProposal)])
+        }
+    }
+
+    #[test]
+    fn test_forge_backend() {
+        super::register_forge("StaticForge", Box::new(StaticForge)).unwrap();
+
+        let forge = super::get_forge_by_hostname("rust-forge.invalid").unwrap();
+        assert_eq!(forge.forge_kind(), "StaticForge");
+        assert_eq!(forge.base_url().as_str(), "https://rust-forge.invalid/");
+        assert!(super::iter_forge_instances().any(|f| f.forge_kind() == "StaticForge"));
+
+        let proposals = forge
+            .iter_my_proposals(Some(super::MergeProposalStatus::All), None)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(proposals.len(), 1);
+        assert!(proposals[0].is_merged().unwrap());
+        assert!(matches!(
+            proposals[0].set_title(Some("Fix it properly")),
+            Err(crate::error::Error::UnsupportedOperation(..))
+        ));
+
+        super::unregister_forge("StaticForge").unwrap();
+    }
+
+    #[test]
+    fn test_proposal_backend() {
+        let proposal = super::MergeProposal::from_backend(Box::new(StaticProposal)).unwrap();
+        assert!(proposal.backend().is_some());
+        assert_eq!(
+            proposal.url().unwrap().as_str(),
+            "https://example.com/proposals/1"
+        );
+        assert_eq!(proposal.get_title().unwrap().as_deref(), Some("Fix it"));
+        assert_eq!(
+            proposal.get_target_branch_url().unwrap().unwrap().as_str(),
+            "https://example.com/trunk"
+        );
+        assert!(proposal.is_merged().unwrap());
+        assert!(!proposal.is_closed().unwrap());
+        assert!(matches!(
+            proposal.set_title(Some("Fix it properly")),
+            Err(crate::error::Error::UnsupportedOperation(..))
+        ));
+
+        assert!(mock_proposal("").backend().is_none());
+    }
 }
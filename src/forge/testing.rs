@@ -0,0 +1,791 @@
+//! A fake forge for testing forge workflows offline.
+//!
+//! [`LocalForge`] registers itself with breezy as the forge for all
+//! `file://` URLs, so [`crate::forge::get_forge`],
+//! [`Forge::publish_derived_as_generic_branch`] and
+//! [`crate::forge::ProposalBuilder`] work on local branches without network
+//! access. Merge proposals are stored as JSON files in a local directory.
+//!
+//! This module is only available with the `testing` feature.
+use crate::branch::{Branch, GenericBranch, PyBranch};
+use crate::error::Error;
+use crate::forge::{unsupported_forge, Forge, MergeProposal, MergeProposalStatus};
+use crate::transport::{add_classmethod, add_method, MethodArgs};
+use crate::RevisionId;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The key [`LocalForge`] is registered under in breezy's forge registry.
+const REGISTRY_KEY: &str = "local";
+
+/// The user that owns all merge proposals on a [`LocalForge`].
+pub const LOCAL_USER: &str = "local";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredComment {
+    author: String,
+    body: String,
+    created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredProposal {
+    id: u64,
+    source_branch_url: String,
+    target_branch_url: String,
+    title: Option<String>,
+    description: Option<String>,
+    commit_message: Option<String>,
+    labels: Vec<String>,
+    reviewers: Vec<String>,
+    work_in_progress: bool,
+    status: String,
+    merged_by: Option<String>,
+    merged_at: Option<String>,
+    comments: Vec<StoredComment>,
+}
+
+impl StoredProposal {
+    fn status(&self) -> MergeProposalStatus {
+        self.status.parse().unwrap_or(MergeProposalStatus::Open)
+    }
+
+    fn matches(&self, status: MergeProposalStatus) -> bool {
+        status == MergeProposalStatus::All || self.status() == status
+    }
+}
+
+/// The directory a [`LocalForge`] keeps its data in.
+struct Store {
+    dir: PathBuf,
+}
+
+impl Store {
+    fn proposals_dir(&self) -> PathBuf {
+        self.dir.join("proposals")
+    }
+
+    fn proposal_path(&self, id: u64) -> PathBuf {
+        self.proposals_dir().join(format!("{}.json", id))
+    }
+
+    fn proposal_url(&self, id: u64) -> String {
+        url::Url::from_file_path(self.proposal_path(id))
+            .unwrap()
+            .to_string()
+    }
+
+    /// Find the id of the proposal at a URL, if it is one of ours.
+    fn proposal_id(&self, url: &str) -> Option<u64> {
+        let path = url::Url::parse(url).ok()?.to_file_path().ok()?;
+        if path.parent()? != self.proposals_dir() {
+            return None;
+        }
+        let id = path.file_stem()?.to_str()?.parse().ok()?;
+        self.proposal_path(id).exists().then_some(id)
+    }
+
+    fn branch_path(&self, owner: Option<&str>, name: &str) -> PathBuf {
+        self.dir
+            .join("branches")
+            .join(owner.unwrap_or(LOCAL_USER))
+            .join(name)
+    }
+
+    fn load(&self, id: u64) -> PyResult<StoredProposal> {
+        let data = std::fs::read(self.proposal_path(id))?;
+        serde_json::from_slice(&data).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    fn save(&self, proposal: &StoredProposal) -> PyResult<()> {
+        let data = serde_json::to_vec_pretty(proposal)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        std::fs::write(self.proposal_path(proposal.id), data)?;
+        Ok(())
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut StoredProposal)) -> PyResult<()> {
+        let mut proposal = self.load(id)?;
+        f(&mut proposal);
+        self.save(&proposal)
+    }
+
+    /// Load all proposals, ordered by id.
+    fn list(&self) -> PyResult<Vec<StoredProposal>> {
+        let mut ids = Vec::new();
+        for entry in std::fs::read_dir(self.proposals_dir())? {
+            let path = entry?.path();
+            if let Some(id) = path.file_stem().and_then(|s| s.to_str()?.parse().ok()) {
+                ids.push(id);
+            }
+        }
+        ids.sort();
+        ids.into_iter().map(|id| self.load(id)).collect()
+    }
+}
+
+fn is_local(branch: &Bound<PyAny>) -> PyResult<bool> {
+    Ok(branch
+        .getattr("user_url")?
+        .extract::<String>()?
+        .starts_with("file://"))
+}
+
+fn branch_url(branch: &Bound<PyAny>) -> PyResult<String> {
+    branch.getattr("user_url")?.extract()
+}
+
+fn same_url(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+fn open_branch(url: &str) -> PyResult<GenericBranch> {
+    let url = url
+        .parse()
+        .map_err(|e: url::ParseError| PyValueError::new_err(e.to_string()))?;
+    Ok(crate::branch::open_as_generic(&url)?)
+}
+
+/// Look up the stored proposal for a Python proposal object.
+fn proposal_id(store: &Store, args: &MethodArgs) -> PyResult<u64> {
+    let url: String = args.this()?.getattr("url")?.extract()?;
+    store
+        .proposal_id(&url)
+        .ok_or_else(|| unsupported_forge(args.py(), url))
+}
+
+fn proposal_class<'py>(py: Python<'py>, store: Arc<Store>) -> PyResult<Bound<'py, PyAny>> {
+    let base_class = py.import("breezy.forge")?.getattr("MergeProposal")?;
+    let namespace = PyDict::new(py);
+    namespace.set_item("supports_auto_merge", false)?;
+
+    add_method(&namespace, "__init__", |args| {
+        args.this()?.setattr("url", args.required(1, "url")?)
+    })?;
+
+    add_method(&namespace, "get_web_url", |args| {
+        Ok(args.this()?.getattr("url")?.unbind())
+    })?;
+
+    macro_rules! getter {
+        ($name:expr, |$p:ident| $value:expr) => {
+            let s = store.clone();
+            add_method(&namespace, $name, move |args| {
+                let $p = s.load(proposal_id(&s, args)?)?;
+                Ok($value)
+            })?;
+        };
+    }
+
+    getter!("get_title", |p| p.title);
+    getter!("get_description", |p| p.description);
+    getter!("get_commit_message", |p| p.commit_message);
+    getter!("get_source_branch_url", |p| p.source_branch_url);
+    getter!("get_target_branch_url", |p| p.target_branch_url);
+    getter!("get_merged_by", |p| p.merged_by);
+    getter!("is_merged", |p| p.status() == MergeProposalStatus::Merged);
+    getter!("is_closed", |p| p.status() == MergeProposalStatus::Closed);
+
+    let s = store.clone();
+    add_method(&namespace, "get_merged_at", move |args| {
+        let proposal = s.load(proposal_id(&s, args)?)?;
+        proposal
+            .merged_at
+            .map(|d| {
+                chrono::DateTime::parse_from_rfc3339(&d)
+                    .map(|d| d.with_timezone(&chrono::Utc))
+                    .map_err(|e| PyValueError::new_err(e.to_string()))
+            })
+            .transpose()
+    })?;
+
+    macro_rules! setter {
+        ($name:expr, $arg:expr, |$p:ident, $v:ident: $t:ty| $update:expr) => {
+            let s = store.clone();
+            add_method(&namespace, $name, move |args| {
+                let $v: $t = match args.get(1, $arg)? {
+                    Some(v) => v.extract()?,
+                    None => Default::default(),
+                };
+                s.update(proposal_id(&s, args)?, |$p| $update)
+            })?;
+        };
+    }
+
+    setter!("set_title", "title", |p, v: Option<String>| p.title = v);
+    setter!("set_description", "description", |p, v: Option<String>| {
+        p.description = v
+    });
+    setter!("set_commit_message", "commit_message", |p,
+                                                     v: Option<
+        String,
+    >| p
+        .commit_message =
+        v);
+    setter!("post_comment", "body", |p, v: String| {
+        p.comments.push(StoredComment {
+            author: LOCAL_USER.to_string(),
+            body: v,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        })
+    });
+
+    let s = store.clone();
+    add_method(&namespace, "get_source_revision", move |args| {
+        let proposal = s.load(proposal_id(&s, args)?)?;
+        Ok(open_branch(&proposal.source_branch_url)?.last_revision())
+    })?;
+
+    let s = store.clone();
+    add_method(&namespace, "close", move |args| {
+        s.update(proposal_id(&s, args)?, |p| {
+            p.status = MergeProposalStatus::Closed.to_string()
+        })
+    })?;
+
+    let s = store.clone();
+    add_method(&namespace, "reopen", move |args| {
+        s.update(proposal_id(&s, args)?, |p| {
+            p.status = MergeProposalStatus::Open.to_string()
+        })
+    })?;
+
+    let s = store.clone();
+    add_method(&namespace, "can_be_merged", move |args| {
+        let py = args.py();
+        let proposal = s.load(proposal_id(&s, args)?)?;
+        let source = open_branch(&proposal.source_branch_url)?;
+        let target = open_branch(&proposal.target_branch_url)?;
+        let target_tip = target.last_revision();
+        if target_tip.is_null() {
+            return Ok(true);
+        }
+        source
+            .to_object(py)
+            .bind(py)
+            .getattr("repository")?
+            .call_method0("get_graph")?
+            .call_method1("is_ancestor", (target_tip, source.last_revision()))?
+            .extract::<bool>()
+    })?;
+
+    // Only fast-forward merges are supported; diverged branches raise
+    // DivergedBranches.
+    let s = store.clone();
+    add_method(&namespace, "merge", move |args| {
+        let id = proposal_id(&s, args)?;
+        let proposal = s.load(id)?;
+        if proposal.status() != MergeProposalStatus::Open {
+            return Err(PyValueError::new_err(format!(
+                "merge proposal {} is {}",
+                id, proposal.status
+            )));
+        }
+        let source = open_branch(&proposal.source_branch_url)?;
+        let target = open_branch(&proposal.target_branch_url)?;
+        target.pull(&source, Some(false))?;
+        s.update(id, |p| {
+            p.status = MergeProposalStatus::Merged.to_string();
+            p.merged_by = Some(LOCAL_USER.to_string());
+            p.merged_at = Some(chrono::Utc::now().to_rfc3339());
+        })
+    })?;
+
+    py.import("builtins")?
+        .getattr("type")?
+        .call1(("LocalMergeProposal", (base_class,), namespace))
+}
+
+fn builder_class<'py>(
+    py: Python<'py>,
+    store: Arc<Store>,
+    proposal_class: Py<PyAny>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let base_class = py.import("breezy.forge")?.getattr("MergeProposalBuilder")?;
+    let namespace = PyDict::new(py);
+
+    add_method(&namespace, "__init__", |args| {
+        let this = args.this()?;
+        this.setattr("source_branch", args.required(1, "source_branch")?)?;
+        this.setattr("target_branch", args.required(2, "target_branch")?)?;
+        Ok(())
+    })?;
+
+    add_method(&namespace, "get_initial_body", |args| Ok(args.py().None()))?;
+
+    add_method(&namespace, "get_infotext", |_args| Ok(""))?;
+
+    add_method(&namespace, "create_proposal", move |args| {
+        let py = args.py();
+        let this = args.this()?;
+        let source_branch_url = branch_url(&this.getattr("source_branch")?)?;
+        let target_branch_url = branch_url(&this.getattr("target_branch")?)?;
+        let proposals = store.list()?;
+        if let Some(existing) = proposals.iter().find(|p| {
+            p.status() == MergeProposalStatus::Open
+                && same_url(&p.source_branch_url, &source_branch_url)
+                && same_url(&p.target_branch_url, &target_branch_url)
+        }) {
+            let existing = proposal_class.call1(py, (store.proposal_url(existing.id),))?;
+            return Err(PyErr::from_value(
+                py.import("breezy.forge")?
+                    .getattr("MergeProposalExists")?
+                    .call1((&source_branch_url, existing))?,
+            ));
+        }
+        let string = |index, name| -> PyResult<Option<String>> {
+            args.get(index, name)?.map(|v| v.extract()).transpose()
+        };
+        let strings = |index, name| -> PyResult<Vec<String>> {
+            Ok(args
+                .get(index, name)?
+                .map(|v| v.extract())
+                .transpose()?
+                .unwrap_or_default())
+        };
+        let proposal = StoredProposal {
+            id: proposals.iter().map(|p| p.id).max().unwrap_or(0) + 1,
+            source_branch_url,
+            target_branch_url,
+            description: string(1, "description")?,
+            title: string(2, "title")?,
+            reviewers: strings(3, "reviewers")?,
+            labels: strings(4, "labels")?,
+            commit_message: string(6, "commit_message")?,
+            work_in_progress: args
+                .get(7, "work_in_progress")?
+                .map(|v| v.extract())
+                .transpose()?
+                .unwrap_or(false),
+            status: MergeProposalStatus::Open.to_string(),
+            merged_by: None,
+            merged_at: None,
+            comments: Vec::new(),
+        };
+        store.save(&proposal)?;
+        proposal_class.call1(py, (store.proposal_url(proposal.id),))
+    })?;
+
+    py.import("builtins")?.getattr("type")?.call1((
+        "LocalMergeProposalBuilder",
+        (base_class,),
+        namespace,
+    ))
+}
+
+fn forge_class<'py>(py: Python<'py>, store: Arc<Store>) -> PyResult<Bound<'py, PyAny>> {
+    let base_class = py.import("breezy.forge")?.getattr("Forge")?;
+    let proposal_class = proposal_class(py, store.clone())?.unbind();
+    let builder_class = builder_class(py, store.clone(), proposal_class.clone_ref(py))?.unbind();
+    let namespace = PyDict::new(py);
+    namespace.set_item("supports_merge_proposal_labels", true)?;
+    namespace.set_item("supports_merge_proposal_title", true)?;
+    namespace.set_item("supports_merge_proposal_commit_message", true)?;
+    namespace.set_item("supports_allow_collaboration", false)?;
+    namespace.set_item("merge_proposal_description_format", "markdown")?;
+
+    let base_url = url::Url::from_directory_path(&store.dir)
+        .map_err(|_| PyValueError::new_err(format!("invalid path: {}", store.dir.display())))?
+        .to_string();
+    let fget = pyo3::types::PyCFunction::new_closure(
+        py,
+        None,
+        None,
+        move |_args: &Bound<PyTuple>, _kwargs: Option<&Bound<PyDict>>| -> PyResult<String> {
+            Ok(base_url.clone())
+        },
+    )?;
+    namespace.set_item(
+        "base_url",
+        py.import("builtins")?.getattr("property")?.call1((fget,))?,
+    )?;
+
+    add_classmethod(&namespace, "probe_from_branch", |args| {
+        let branch = args.required(1, "branch")?;
+        if !is_local(&branch)? {
+            return Err(unsupported_forge(args.py(), branch));
+        }
+        Ok(args.this()?.call0()?.unbind())
+    })?;
+
+    add_classmethod(&namespace, "probe_from_url", |args| {
+        let url: String = args.required(1, "url")?.extract()?;
+        if !url.starts_with("file://") {
+            return Err(unsupported_forge(args.py(), url));
+        }
+        Ok(args.this()?.call0()?.unbind())
+    })?;
+
+    add_classmethod(&namespace, "probe_from_hostname", |args| {
+        Err::<(), _>(unsupported_forge(args.py(), args.required(1, "hostname")?))
+    })?;
+
+    add_classmethod(&namespace, "iter_instances", |args| {
+        Ok(vec![args.this()?.call0()?.unbind()])
+    })?;
+
+    add_method(&namespace, "hosts", |args| {
+        is_local(&args.required(1, "branch")?)
+    })?;
+
+    add_method(&namespace, "get_push_url", |args| {
+        branch_url(&args.required(1, "branch")?)
+    })?;
+
+    add_method(&namespace, "get_web_url", |args| {
+        branch_url(&args.required(1, "branch")?)
+    })?;
+
+    add_method(&namespace, "get_current_user", |_args| Ok(LOCAL_USER))?;
+
+    let s = store.clone();
+    add_method(&namespace, "get_user_url", move |args| {
+        let user: String = args.required(1, "user")?.extract()?;
+        Ok(
+            url::Url::from_directory_path(s.dir.join("users").join(user))
+                .map_err(|_| PyValueError::new_err("invalid user"))?
+                .to_string(),
+        )
+    })?;
+
+    add_method(&namespace, "get_proposer", move |args| {
+        builder_class.call1(
+            args.py(),
+            (
+                args.required(1, "source_branch")?,
+                args.required(2, "target_branch")?,
+            ),
+        )
+    })?;
+
+    let s = store.clone();
+    let p = proposal_class.clone_ref(py);
+    add_method(&namespace, "get_proposal_by_url", move |args| {
+        let url: String = args.required(1, "url")?.extract()?;
+        match s.proposal_id(&url) {
+            Some(id) => p.call1(args.py(), (s.proposal_url(id),)),
+            None => Err(unsupported_forge(args.py(), url)),
+        }
+    })?;
+
+    let s = store.clone();
+    let p = proposal_class.clone_ref(py);
+    add_method(&namespace, "iter_proposals", move |args| {
+        let source_branch_url = branch_url(&args.required(1, "source_branch")?)?;
+        let target_branch_url = branch_url(&args.required(2, "target_branch")?)?;
+        let status: MergeProposalStatus = match args.get(3, "status")? {
+            Some(status) => status.extract()?,
+            None => MergeProposalStatus::Open,
+        };
+        s.list()?
+            .into_iter()
+            .filter(|proposal| {
+                proposal.matches(status)
+                    && same_url(&proposal.source_branch_url, &source_branch_url)
+                    && same_url(&proposal.target_branch_url, &target_branch_url)
+            })
+            .map(|proposal| p.call1(args.py(), (s.proposal_url(proposal.id),)))
+            .collect::<PyResult<Vec<_>>>()
+    })?;
+
+    let s = store.clone();
+    let p = proposal_class;
+    add_method(&namespace, "iter_my_proposals", move |args| {
+        let status: MergeProposalStatus = match args.get(1, "status")? {
+            Some(status) => status.extract()?,
+            None => MergeProposalStatus::Open,
+        };
+        let author: Option<String> = args.get(2, "author")?.map(|a| a.extract()).transpose()?;
+        if author.is_some_and(|a| a != LOCAL_USER) {
+            return Ok(Vec::new());
+        }
+        s.list()?
+            .into_iter()
+            .filter(|proposal| proposal.matches(status))
+            .map(|proposal| p.call1(args.py(), (s.proposal_url(proposal.id),)))
+            .collect::<PyResult<Vec<_>>>()
+    })?;
+
+    let s = store.clone();
+    add_method(&namespace, "publish_derived", move |args| {
+        let py = args.py();
+        let local_branch = GenericBranch::from(args.required(1, "local_branch")?.unbind());
+        let base_branch = GenericBranch::from(args.required(2, "base_branch")?.unbind());
+        let name: String = args.required(3, "name")?.extract()?;
+        let owner: Option<String> = args.get(5, "owner")?.map(|o| o.extract()).transpose()?;
+        let revision_id: Option<RevisionId> = args
+            .get(6, "revision_id")?
+            .map(|r| r.extract())
+            .transpose()?;
+        let overwrite: bool = args
+            .get(7, "overwrite")?
+            .map(|o| o.extract())
+            .transpose()?
+            .unwrap_or(false);
+        let tag_selector = args.get(9, "tag_selector")?.map(|f| {
+            let f = f.unbind();
+            Box::new(move |tag: String| {
+                Python::attach(|py| {
+                    f.call1(py, (tag,))
+                        .and_then(|r| r.extract(py))
+                        .unwrap_or(false)
+                })
+            }) as Box<dyn Fn(String) -> bool>
+        });
+
+        let path = s.branch_path(owner.as_deref(), &name);
+        let controldir = match crate::controldir::open(path.as_path(), None) {
+            Ok(controldir) => controldir,
+            Err(Error::NotBranchError(..)) => {
+                std::fs::create_dir_all(&path)?;
+                crate::controldir::create(
+                    path.as_path(),
+                    &base_branch.controldir().cloning_metadir(),
+                    None,
+                )?
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let branch = controldir.push_branch(
+            &local_branch,
+            None,
+            revision_id.as_ref(),
+            Some(overwrite),
+            tag_selector,
+        )?;
+        let url = branch_url(branch.to_object(py).bind(py))?;
+        Ok((branch.to_object(py), url))
+    })?;
+
+    let s = store;
+    add_method(&namespace, "get_derived_branch", move |args| {
+        let name: String = args.required(2, "name")?.extract()?;
+        let owner: Option<String> = args.get(4, "owner")?.map(|o| o.extract()).transpose()?;
+        let url = url::Url::from_directory_path(s.branch_path(owner.as_deref(), &name))
+            .map_err(|_| PyValueError::new_err(format!("invalid branch name: {}", name)))?;
+        Ok(crate::branch::open_as_generic(&url)?.to_object(args.py()))
+    })?;
+
+    py.import("builtins")?
+        .getattr("type")?
+        .call1(("LocalForge", (base_class,), namespace))
+}
+
+/// A forge that keeps merge proposals in a local directory.
+///
+/// While a `LocalForge` exists it is registered with breezy as the forge for
+/// all `file://` URLs; it is unregistered when dropped. Only one
+/// `LocalForge` should exist at a time.
+///
+/// Derived branches are published below `branches/<owner>/<name>` in the
+/// forge directory, and merge proposals are stored below `proposals/`.
+/// Merging a proposal fast-forwards the target branch to the source branch,
+/// and fails with [`Error::DivergedBranches`] if that is not possible.
+pub struct LocalForge {
+    dir: PathBuf,
+    forge: Forge,
+}
+
+impl LocalForge {
+    /// Create a local forge and register it with breezy.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to store derived branches and merge proposals
+    ///   in. It is created if it does not exist.
+    pub fn new(dir: &Path) -> Result<Self, Error> {
+        std::fs::create_dir_all(dir.join("proposals")).map_err(|e| Error::Other(e.into()))?;
+        let dir = dir.canonicalize().map_err(|e| Error::Other(e.into()))?;
+        let store = Arc::new(Store { dir: dir.clone() });
+        Python::attach(|py| {
+            let class = forge_class(py, store)?;
+            let forges = py.import("breezy.forge")?.getattr("forges")?;
+            if forges.contains(REGISTRY_KEY)? {
+                forges.call_method1("remove", (REGISTRY_KEY,))?;
+            }
+            forges.call_method1("register", (REGISTRY_KEY, &class))?;
+            Ok(LocalForge {
+                dir,
+                forge: Forge::from(class.call0()?.unbind()),
+            })
+        })
+    }
+
+    /// The directory the forge stores its data in.
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The forge, as returned by [`crate::forge::get_forge`] for local branches.
+    pub fn forge(&self) -> Forge {
+        self.forge.clone()
+    }
+
+    /// The comments posted on a merge proposal, oldest first.
+    ///
+    /// Breezy has no API to read comments back, so this reads them from the
+    /// forge directory.
+    ///
+    /// # Returns
+    ///
+    /// The author and body of each comment
+    pub fn comments(&self, proposal: &MergeProposal) -> Result<Vec<(String, String)>, Error> {
+        Ok(self
+            .load(proposal)?
+            .comments
+            .into_iter()
+            .map(|c| (c.author, c.body))
+            .collect())
+    }
+
+    /// The labels of a merge proposal.
+    ///
+    /// Breezy can only set labels when creating a proposal, so this reads
+    /// them from the forge directory.
+    pub fn labels(&self, proposal: &MergeProposal) -> Result<Vec<String>, Error> {
+        Ok(self.load(proposal)?.labels)
+    }
+
+    fn load(&self, proposal: &MergeProposal) -> Result<StoredProposal, Error> {
+        let store = Store {
+            dir: self.dir.clone(),
+        };
+        let url = proposal.url()?;
+        let id = store
+            .proposal_id(url.as_str())
+            .ok_or(Error::UnsupportedForge(url))?;
+        Ok(store.load(id)?)
+    }
+
+    /// All merge proposals on the forge, in the order they were created.
+    pub fn proposals(&self) -> Result<Vec<MergeProposal>, Error> {
+        Ok(self
+            .forge
+            .iter_my_proposals(Some(MergeProposalStatus::All), None)?
+            .collect())
+    }
+}
+
+impl Drop for LocalForge {
+    fn drop(&mut self) {
+        Python::attach(|py| {
+            if let Ok(forges) = py.import("breezy.forge").and_then(|m| m.getattr("forges")) {
+                if forges.contains(REGISTRY_KEY).unwrap_or(false) {
+                    let _ = forges.call_method1("remove", (REGISTRY_KEY,));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+    use crate::tree::MutableTree;
+    use crate::workingtree::WorkingTree;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_proposal_lifecycle() {
+        let env = crate::testing::TestEnv::new();
+        let trunk = create_standalone_workingtree(Path::new("trunk"), &ControlDirFormat::default())
+            .unwrap();
+        std::fs::write("trunk/a", b"a\n").unwrap();
+        trunk.add(&[Path::new("a")]).unwrap();
+        trunk.build_commit().message("Add a").commit().unwrap();
+        let feature = trunk
+            .controldir()
+            .sprout(
+                url::Url::from_directory_path(env.working_dir.join("feature")).unwrap(),
+                None,
+                Some(true),
+                None,
+                None,
+            )
+            .unwrap()
+            .open_workingtree()
+            .unwrap();
+        std::fs::write("feature/a", b"a\nb\n").unwrap();
+        let feature_revid = feature.build_commit().message("Add b").commit().unwrap();
+
+        let local_forge = LocalForge::new(Path::new("forge")).unwrap();
+        let forge = crate::forge::get_forge(&trunk.branch()).unwrap();
+        assert_eq!(forge.forge_kind(), "LocalForge");
+
+        let (derived, _url) = forge
+            .publish_derived_as_generic_branch(
+                &feature.branch(),
+                &trunk.branch(),
+                "feature",
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(derived.last_revision(), feature_revid);
+
+        let proposal = forge
+            .get_proposer(&derived, &trunk.branch())
+            .unwrap()
+            .title("Add b")
+            .description("Adds b.")
+            .labels(&["enhancement"])
+            .build()
+            .unwrap();
+        assert_eq!(proposal.get_title().unwrap().as_deref(), Some("Add b"));
+        assert_eq!(local_forge.labels(&proposal).unwrap(), vec!["enhancement"]);
+        assert_eq!(local_forge.proposals().unwrap().len(), 1);
+        assert!(matches!(
+            forge
+                .get_proposer(&derived, &trunk.branch())
+                .unwrap()
+                .description("Again")
+                .build(),
+            Err(Error::MergeProposalExists(..))
+        ));
+
+        proposal.post_comment("Looks good").unwrap();
+        assert_eq!(
+            local_forge.comments(&proposal).unwrap(),
+            vec![(LOCAL_USER.to_string(), "Looks good".to_string())]
+        );
+
+        proposal.close().unwrap();
+        assert!(proposal.is_closed().unwrap());
+        assert_eq!(
+            forge
+                .iter_my_proposals(Some(MergeProposalStatus::Open), None)
+                .unwrap()
+                .count(),
+            0
+        );
+        proposal.reopen().unwrap();
+        assert!(!proposal.is_closed().unwrap());
+
+        assert!(proposal.can_be_merged().unwrap());
+        proposal.merge(false).unwrap();
+        assert!(proposal.is_merged().unwrap());
+        assert_eq!(
+            proposal.get_merged_by().unwrap().as_deref(),
+            Some(LOCAL_USER)
+        );
+        assert!(proposal.get_merged_at().unwrap().is_some());
+        assert_eq!(trunk.branch().last_revision(), feature_revid);
+
+        let reopened = crate::forge::get_proposal_by_url(&proposal.url().unwrap()).unwrap();
+        assert_eq!(
+            reopened.get_description().unwrap().as_deref(),
+            Some("Adds b.")
+        );
+
+        std::mem::drop(local_forge);
+        std::mem::drop(env);
+    }
+}
@@ -1,5 +1,5 @@
 //! GPG related functions and types.
-use crate::repository::PyRepository;
+use crate::repository::{PyRepository, Repository};
 use crate::RevisionId;
 use pyo3::import_exception;
 use pyo3::prelude::*;
@@ -9,6 +9,10 @@ use pyo3::prelude::*;
 pub enum Error {
     /// GPG is not installed on the system.
     GPGNotInstalled,
+    /// Signing failed, for example because no suitable key is available.
+    SigningFailed(String),
+    /// Any other error raised by the underlying Python code.
+    Other(PyErr),
 }
 
 #[derive(Debug)]
@@ -38,14 +42,17 @@ pub enum Status {
 }
 
 import_exception!(breezy.gpg, GPGNotInstalled);
+import_exception!(breezy.gpg, SigningFailed);
 
 impl From<PyErr> for Error {
     fn from(e: PyErr) -> Self {
         Python::attach(|py| {
             if e.is_instance_of::<GPGNotInstalled>(py) {
                 Error::GPGNotInstalled
+            } else if e.is_instance_of::<SigningFailed>(py) {
+                Error::SigningFailed(e.value(py).to_string())
             } else {
-                panic!("unexpected exception: {:?}", e)
+                Error::Other(e)
             }
         })
     }
@@ -55,7 +62,7 @@ impl From<PyErr> for Error {
 pub struct GPGStrategy(Py<PyAny>);
 
 impl GPGStrategy {
-    fn to_object(&self) -> &Py<PyAny> {
+    pub(crate) fn to_object(&self) -> &Py<PyAny> {
         &self.0
     }
     /// Create a new GPG strategy with the given branch configuration.
@@ -69,6 +76,43 @@ impl GPGStrategy {
         })
     }
 
+    /// Create a new GPG strategy with the given configuration stack.
+    ///
+    /// The `gpg_signing_key` option selects the key used for signing.
+    pub fn from_config_stack(config: &crate::config::ConfigStack) -> Self {
+        Python::attach(|py| {
+            let gpg = PyModule::import(py, "breezy.gpg").unwrap();
+            let gpg_strategy = gpg.getattr("GPGStrategy").unwrap();
+            let strategy = gpg_strategy.call1((config.as_pyobject(),)).unwrap();
+            GPGStrategy(strategy.unbind())
+        })
+    }
+
+    /// Sign content.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content to sign
+    /// * `mode` - Whether to create a normal, detached or clear signature
+    ///
+    /// # Returns
+    ///
+    /// The signed content, or the signature alone in [`Mode::Detach`] mode.
+    pub fn sign(&self, content: &[u8], mode: Mode) -> Result<Vec<u8>, Error> {
+        Python::attach(|py| {
+            let gpg = PyModule::import(py, "breezy.gpg")?;
+            let mode = gpg.getattr(match mode {
+                Mode::Normal => "MODE_NORMAL",
+                Mode::Detach => "MODE_DETACH",
+                Mode::Clear => "MODE_CLEAR",
+            })?;
+            Ok(self
+                .0
+                .call_method1(py, "sign", (pyo3::types::PyBytes::new(py, content), mode))?
+                .extract(py)?)
+        })
+    }
+
     /// Set the GPG keys that are acceptable for validating signatures.
     pub fn set_acceptable_keys(&self, keys: &[String]) {
         Python::attach(|py| {
@@ -204,6 +248,67 @@ pub fn bulk_verify_signatures<R: PyRepository>(
     })
 }
 
+/// Sign all unsigned revisions in the history of a branch by a committer.
+///
+/// The signatures are added in a single write group, so either all of them
+/// are stored or none are.
+///
+/// # Arguments
+///
+/// * `branch` - The branch whose history to sign
+/// * `strategy` - GPG strategy to sign with
+/// * `committer_filter` - Selects the revisions to sign by their committer
+///
+/// # Returns
+///
+/// The revisions that were signed
+pub fn sign_my_commits(
+    branch: &dyn crate::branch::Branch,
+    strategy: &GPGStrategy,
+    committer_filter: impl Fn(&str) -> bool,
+) -> Result<Vec<RevisionId>, crate::error::Error> {
+    let repository = branch.repository();
+    let _lock = repository.lock_write()?;
+    let graph = repository.get_graph();
+    let mut candidates = vec![];
+    for item in graph.iter_ancestry(&[branch.last_revision()])? {
+        if let (revid, Some(_)) = item? {
+            if !revid.is_null() {
+                candidates.push(revid);
+            }
+        }
+    }
+    repository.start_write_group()?;
+    let sign = || -> Result<Vec<RevisionId>, crate::error::Error> {
+        let mut signed = vec![];
+        for revid in candidates {
+            if repository.has_signature_for_revision_id(&revid)? {
+                continue;
+            }
+            if !committer_filter(&repository.get_revision(&revid)?.committer) {
+                continue;
+            }
+            repository.sign_revision(&revid, strategy)?;
+            signed.push(revid);
+        }
+        Ok(signed)
+    };
+    match sign() {
+        Ok(signed) => {
+            repository.commit_write_group()?;
+            Ok(signed)
+        }
+        Err(e) => {
+            // Report the error that caused the abort rather than any error
+            // from aborting.
+            if let Err(abort_err) = repository.abort_write_group() {
+                log::warn!("Failed to abort write group: {}", abort_err);
+            }
+            Err(e)
+        }
+    }
+}
+
 /// Context for interacting with GPG.
 pub struct GPGContext(Py<PyAny>);
 
@@ -272,3 +377,64 @@ impl GPGContext {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch::Branch;
+    use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+    use crate::workingtree::WorkingTree;
+    use serial_test::serial;
+    use std::path::Path;
+
+    /// Create a signing key in a throwaway keyring in the test home
+    /// directory.
+    fn create_key(env: &crate::testing::TestEnv) {
+        let gnupghome = env.home_dir.join(".gnupg");
+        std::fs::create_dir(&gnupghome).unwrap();
+        let output = std::process::Command::new("gpg")
+            .arg("--homedir")
+            .arg(&gnupghome)
+            .args(["--batch", "--passphrase", "", "--quick-gen-key"])
+            .args(["Joe Tester <joe@example.com>", "default", "sign", "never"])
+            .output()
+            .expect("gpg is not installed");
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    #[test]
+    #[serial]
+    #[ignore] // Requires gpg to be installed
+    fn test_sign_my_commits() {
+        let env = crate::testing::TestEnv::new();
+        create_key(&env);
+        let wt =
+            create_standalone_workingtree(Path::new("."), &ControlDirFormat::default()).unwrap();
+        let mine = wt
+            .build_commit()
+            .message("Mine")
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap();
+        let theirs = wt
+            .build_commit()
+            .message("Theirs")
+            .committer("Jane Example <jane@example.com>")
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap();
+        let branch = wt.branch();
+        let strategy = GPGStrategy::from_config_stack(&branch.get_config_stack());
+
+        let signed = strategy.sign(b"content\n", Mode::Clear).unwrap();
+        assert!(signed.starts_with(b"-----BEGIN PGP SIGNED MESSAGE-----"));
+
+        let signed =
+            sign_my_commits(&branch, &strategy, |c| c == "Joe Tester <joe@example.com>").unwrap();
+        assert_eq!(signed, vec![mine.clone()]);
+        let repository = branch.repository();
+        assert!(repository.has_signature_for_revision_id(&mine).unwrap());
+        assert!(!repository.has_signature_for_revision_id(&theirs).unwrap());
+        std::mem::drop(env);
+    }
+}
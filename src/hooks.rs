@@ -1,12 +1,37 @@
 //! Hooks
+//!
+//! Besides the raw [`HookDict`], this module provides typed registration for
+//! the most common hook points. Each `install_*_hook` function takes a Rust
+//! closure and returns a [`HookGuard`] that uninstalls the hook again when it
+//! is dropped.
+use crate::branch::{GenericBranch, PyBranch};
+use crate::delta::TreeDelta;
+use crate::error::Error;
+use crate::merge::{MergeFileParams, MergeFileResult};
+use crate::tree::{GenericMutableTree, GenericTree};
+use crate::RevisionId;
 use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use std::sync::Arc;
 
 /// Dictionary-like container for Breezy hooks.
 pub struct HookDict(Py<PyAny>);
 
+impl Clone for HookDict {
+    fn clone(&self) -> Self {
+        Python::attach(|py| HookDict(self.0.clone_ref(py)))
+    }
+}
+
 /// Represents an individual hook function.
 pub struct Hook(Py<PyAny>);
 
+impl From<Py<PyAny>> for Hook {
+    fn from(obj: Py<PyAny>) -> Self {
+        Hook(obj)
+    }
+}
+
 impl HookDict {
     /// Create a new hook dictionary.
     ///
@@ -60,6 +85,48 @@ impl HookDict {
         })
     }
 
+    /// Install a hook function under a label.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the hook point
+    /// * `func` - The hook function to install
+    /// * `label` - A label identifying the hook, used to uninstall it
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an error if the operation fails
+    pub fn install_named_hook(
+        &self,
+        name: &str,
+        func: Hook,
+        label: &str,
+    ) -> Result<(), crate::error::Error> {
+        Python::attach(|py| {
+            self.0
+                .call_method1(py, "install_named_hook", (name, func.0, label))?;
+            Ok(())
+        })
+    }
+
+    /// Uninstall a hook function that was installed under a label.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the hook point
+    /// * `label` - The label the hook was installed with
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an error if the operation fails
+    pub fn uninstall_named_hook(&self, name: &str, label: &str) -> Result<(), crate::error::Error> {
+        Python::attach(|py| {
+            self.0
+                .call_method1(py, "uninstall_named_hook", (name, label))?;
+            Ok(())
+        })
+    }
+
     /// Get all hook functions registered for a given name.
     ///
     /// # Arguments
@@ -79,6 +146,446 @@ impl HookDict {
                 .collect::<PyResult<Vec<_>>>()?)
         })
     }
+
+    /// Install a Rust closure as a hook.
+    ///
+    /// The closure receives the positional arguments the hook is called with.
+    pub(crate) fn install_closure<R>(
+        &self,
+        name: &str,
+        label: &str,
+        f: impl Fn(&Bound<PyTuple>) -> PyResult<R> + Send + Sync + 'static,
+    ) -> Result<HookGuard, Error>
+    where
+        R: for<'py> IntoPyObject<'py>,
+    {
+        let func = Python::attach(|py| {
+            pyo3::types::PyCFunction::new_closure(
+                py,
+                None,
+                None,
+                move |args: &Bound<PyTuple>, _kwargs: Option<&Bound<PyDict>>| f(args),
+            )
+            .map(|f| f.into_any().unbind())
+        })?;
+        self.install_named_hook(name, Hook(func), label)?;
+        Ok(HookGuard {
+            hooks: self.clone(),
+            name: name.to_string(),
+            label: label.to_string(),
+        })
+    }
+}
+
+/// Uninstalls a hook when dropped.
+#[must_use = "the hook is uninstalled when the guard is dropped"]
+pub struct HookGuard {
+    hooks: HookDict,
+    name: String,
+    label: String,
+}
+
+impl HookGuard {
+    /// The name of the hook point the hook is installed on.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The label the hook was installed with.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        // There is nothing sensible to do if the hook has already been
+        // removed, e.g. by clearing the hook point.
+        let _ = self.hooks.uninstall_named_hook(&self.name, &self.label);
+    }
+}
+
+fn branch_hooks() -> HookDict {
+    HookDict::new("breezy.branch", "Branch", "hooks")
+}
+
+/// Parameters of the `pre_commit` branch hook.
+pub struct PreCommitParams {
+    /// The local branch, if the branch being committed to is bound.
+    pub local: Option<GenericBranch>,
+    /// The branch being committed to.
+    pub master: GenericBranch,
+    /// The revision number of the tip before the commit.
+    pub old_revno: u32,
+    /// The tip before the commit.
+    pub old_revid: RevisionId,
+    /// The revision number the new revision will get.
+    pub future_revno: u32,
+    /// The revision id the new revision will get.
+    pub future_revid: RevisionId,
+    /// The changes being committed.
+    pub tree_delta: TreeDelta,
+    /// The tree as it will be after the commit.
+    pub future_tree: GenericTree,
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for PreCommitParams {
+    type Error = PyErr;
+
+    fn extract(ob: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        let (
+            local,
+            master,
+            old_revno,
+            old_revid,
+            future_revno,
+            future_revid,
+            tree_delta,
+            future_tree,
+        ) = ob.extract::<(_, _, _, _, _, _, _, Py<PyAny>)>()?;
+        Ok(PreCommitParams {
+            local,
+            master,
+            old_revno,
+            old_revid,
+            future_revno,
+            future_revid,
+            tree_delta,
+            future_tree: GenericTree::from(future_tree),
+        })
+    }
+}
+
+/// Parameters of the `post_commit` branch hook.
+pub struct PostCommitParams {
+    /// The local branch, if the branch committed to is bound.
+    pub local: Option<GenericBranch>,
+    /// The branch committed to.
+    pub master: GenericBranch,
+    /// The revision number of the tip before the commit.
+    pub old_revno: u32,
+    /// The tip before the commit.
+    pub old_revid: RevisionId,
+    /// The revision number of the new revision.
+    pub new_revno: u32,
+    /// The new revision.
+    pub new_revid: RevisionId,
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for PostCommitParams {
+    type Error = PyErr;
+
+    fn extract(ob: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        let (local, master, old_revno, old_revid, new_revno, new_revid) = ob.extract()?;
+        Ok(PostCommitParams {
+            local,
+            master,
+            old_revno,
+            old_revid,
+            new_revno,
+            new_revid,
+        })
+    }
+}
+
+/// Parameters of the `post_change_branch_tip` branch hook.
+pub struct ChangeBranchTipParams {
+    /// The branch whose tip changed.
+    pub branch: GenericBranch,
+    /// The old revision number.
+    pub old_revno: u32,
+    /// The new revision number.
+    pub new_revno: u32,
+    /// The old tip.
+    pub old_revid: RevisionId,
+    /// The new tip.
+    pub new_revid: RevisionId,
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for ChangeBranchTipParams {
+    type Error = PyErr;
+
+    fn extract(ob: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        Ok(ChangeBranchTipParams {
+            branch: ob.getattr("branch")?.extract()?,
+            old_revno: ob.getattr("old_revno")?.extract()?,
+            new_revno: ob.getattr("new_revno")?.extract()?,
+            old_revid: ob.getattr("old_revid")?.extract()?,
+            new_revid: ob.getattr("new_revid")?.extract()?,
+        })
+    }
+}
+
+/// The result of a push or pull, as passed to the `post_push` and
+/// `post_pull` branch hooks.
+pub struct BranchUpdateResult {
+    /// The branch the revisions came from.
+    pub source_branch: GenericBranch,
+    /// The branch that was updated.
+    pub target_branch: GenericBranch,
+    /// The master branch of the target, if it is bound.
+    pub master_branch: Option<GenericBranch>,
+    /// The local branch, if the target is bound.
+    pub local_branch: Option<GenericBranch>,
+    /// The revision number of the tip before the update.
+    pub old_revno: u32,
+    /// The tip before the update.
+    pub old_revid: RevisionId,
+    /// The revision number of the tip after the update.
+    pub new_revno: u32,
+    /// The tip after the update.
+    pub new_revid: RevisionId,
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for BranchUpdateResult {
+    type Error = PyErr;
+
+    fn extract(ob: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        Ok(BranchUpdateResult {
+            source_branch: ob.getattr("source_branch")?.extract()?,
+            target_branch: ob.getattr("target_branch")?.extract()?,
+            master_branch: ob.getattr("master_branch")?.extract()?,
+            local_branch: ob.getattr("local_branch")?.extract()?,
+            old_revno: ob.getattr("old_revno")?.extract()?,
+            old_revid: ob.getattr("old_revid")?.extract()?,
+            new_revno: ob.getattr("new_revno")?.extract()?,
+            new_revid: ob.getattr("new_revid")?.extract()?,
+        })
+    }
+}
+
+/// Parameters of the `start_commit` mutable tree hook.
+pub struct StartCommitParams {
+    /// The tree that is about to be committed.
+    pub tree: GenericMutableTree,
+}
+
+/// Parameters of the `merge_proposal_body` hook.
+pub struct MergeProposalBodyParams {
+    /// The branch the proposal targets.
+    pub target_branch: GenericBranch,
+    /// The body returned by the previous hook, if any.
+    pub old_body: Option<String>,
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for MergeProposalBodyParams {
+    type Error = PyErr;
+
+    fn extract(ob: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        Ok(MergeProposalBodyParams {
+            target_branch: ob.get_item("target_branch")?.extract()?,
+            old_body: ob.get_item("old_body")?.extract()?,
+        })
+    }
+}
+
+/// Parameters of the `get_prerequisite` hook.
+pub struct GetPrerequisiteParams {
+    /// The branch the proposal targets.
+    pub target_branch: GenericBranch,
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for GetPrerequisiteParams {
+    type Error = PyErr;
+
+    fn extract(ob: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        Ok(GetPrerequisiteParams {
+            target_branch: ob.get_item("target_branch")?.extract()?,
+        })
+    }
+}
+
+/// Install a hook that is called before a branch commit is finalized.
+///
+/// Returning an error from the hook aborts the commit.
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the hook
+/// * `f` - The hook
+///
+/// # Returns
+///
+/// A guard that uninstalls the hook when dropped
+pub fn install_pre_commit_hook(
+    label: &str,
+    f: impl Fn(&PreCommitParams) -> Result<(), Error> + Send + Sync + 'static,
+) -> Result<HookGuard, Error> {
+    branch_hooks().install_closure("pre_commit", label, move |args| Ok(f(&args.extract()?)?))
+}
+
+/// Install a hook that is called after a commit to a branch.
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the hook
+/// * `f` - The hook
+///
+/// # Returns
+///
+/// A guard that uninstalls the hook when dropped
+pub fn install_post_commit_hook(
+    label: &str,
+    f: impl Fn(&PostCommitParams) -> Result<(), Error> + Send + Sync + 'static,
+) -> Result<HookGuard, Error> {
+    branch_hooks().install_closure("post_commit", label, move |args| Ok(f(&args.extract()?)?))
+}
+
+/// Install a hook that is called after the tip of a branch changed.
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the hook
+/// * `f` - The hook
+///
+/// # Returns
+///
+/// A guard that uninstalls the hook when dropped
+pub fn install_post_change_branch_tip_hook(
+    label: &str,
+    f: impl Fn(&ChangeBranchTipParams) -> Result<(), Error> + Send + Sync + 'static,
+) -> Result<HookGuard, Error> {
+    branch_hooks().install_closure("post_change_branch_tip", label, move |args| {
+        Ok(f(&args.get_item(0)?.extract()?)?)
+    })
+}
+
+/// Install a hook that is called after a push.
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the hook
+/// * `f` - The hook
+///
+/// # Returns
+///
+/// A guard that uninstalls the hook when dropped
+pub fn install_post_push_hook(
+    label: &str,
+    f: impl Fn(&BranchUpdateResult) -> Result<(), Error> + Send + Sync + 'static,
+) -> Result<HookGuard, Error> {
+    branch_hooks().install_closure("post_push", label, move |args| {
+        Ok(f(&args.get_item(0)?.extract()?)?)
+    })
+}
+
+/// Install a hook that is called after a pull.
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the hook
+/// * `f` - The hook
+///
+/// # Returns
+///
+/// A guard that uninstalls the hook when dropped
+pub fn install_post_pull_hook(
+    label: &str,
+    f: impl Fn(&BranchUpdateResult) -> Result<(), Error> + Send + Sync + 'static,
+) -> Result<HookGuard, Error> {
+    branch_hooks().install_closure("post_pull", label, move |args| {
+        Ok(f(&args.get_item(0)?.extract()?)?)
+    })
+}
+
+/// Install a hook that is called before a mutable tree is committed.
+///
+/// The hook may still modify the tree. Returning an error aborts the commit.
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the hook
+/// * `f` - The hook
+///
+/// # Returns
+///
+/// A guard that uninstalls the hook when dropped
+pub fn install_start_commit_hook(
+    label: &str,
+    f: impl Fn(&StartCommitParams) -> Result<(), Error> + Send + Sync + 'static,
+) -> Result<HookGuard, Error> {
+    HookDict::new("breezy.mutabletree", "MutableTree", "hooks").install_closure(
+        "start_commit",
+        label,
+        move |args| {
+            let tree = GenericMutableTree::from(args.get_item(0)?.unbind());
+            Ok(f(&StartCommitParams { tree })?)
+        },
+    )
+}
+
+/// Install a hook that merges the contents of individual files.
+///
+/// This is the closure equivalent of [`crate::merge::install_per_file_merger`].
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the hook
+/// * `f` - The hook
+///
+/// # Returns
+///
+/// A guard that uninstalls the hook when dropped
+pub fn install_merge_file_content_hook(
+    label: &str,
+    f: impl Fn(&MergeFileParams) -> Result<MergeFileResult, Error> + Send + Sync + 'static,
+) -> Result<HookGuard, Error> {
+    crate::merge::install_per_file_merger(label, Arc::new(f))?;
+    Ok(HookGuard {
+        hooks: crate::merge::Merger::hooks().clone(),
+        name: "merge_file_content".to_string(),
+        label: label.to_string(),
+    })
+}
+
+/// Install a hook that provides the initial body of a merge proposal.
+///
+/// Hooks are called in turn, each receiving the body returned by the
+/// previous one; the hook should return `old_body` to keep it.
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the hook
+/// * `f` - The hook
+///
+/// # Returns
+///
+/// A guard that uninstalls the hook when dropped
+pub fn install_merge_proposal_body_hook(
+    label: &str,
+    f: impl Fn(&MergeProposalBodyParams) -> Result<Option<String>, Error> + Send + Sync + 'static,
+) -> Result<HookGuard, Error> {
+    HookDict::new("breezy.forge", "MergeProposalBuilder", "hooks").install_closure(
+        "merge_proposal_body",
+        label,
+        move |args| Ok(f(&args.get_item(0)?.extract()?)?),
+    )
+}
+
+/// Install a hook that provides the prerequisite branch of a merge proposal.
+///
+/// Hooks are called in turn until one returns a branch.
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the hook
+/// * `f` - The hook
+///
+/// # Returns
+///
+/// A guard that uninstalls the hook when dropped
+pub fn install_get_prerequisite_hook(
+    label: &str,
+    f: impl Fn(&GetPrerequisiteParams) -> Result<Option<GenericBranch>, Error> + Send + Sync + 'static,
+) -> Result<HookGuard, Error> {
+    HookDict::new("breezy.forge", "MergeProposalBuilder", "hooks").install_closure(
+        "get_prerequisite",
+        label,
+        move |args| {
+            let prerequisite = f(&args.get_item(0)?.extract()?)?;
+            Ok(prerequisite.map(|branch| branch.to_object(args.py())))
+        },
+    )
 }
 
 #[cfg(test)]
@@ -146,4 +653,320 @@ mod tests {
         let hooks = merge_hooks();
         assert!(hooks.get("nonexistent_hook").is_err());
     }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_commit_hooks() {
+        use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+        use crate::tree::MutableTree;
+        use crate::workingtree::WorkingTree;
+        use std::sync::Mutex;
+
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(std::path::Path::new("."), &ControlDirFormat::default())
+                .unwrap();
+        std::fs::write("a", b"a\n").unwrap();
+        wt.add(&[std::path::Path::new("a")]).unwrap();
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let c = calls.clone();
+        let start_commit = install_start_commit_hook("breezyshim test start_commit", move |_| {
+            c.lock().unwrap().push("start_commit".to_string());
+            Ok(())
+        })
+        .unwrap();
+        let c = calls.clone();
+        let pre_commit = install_pre_commit_hook("breezyshim test pre_commit", move |params| {
+            assert_eq!(params.old_revno, 0);
+            assert_eq!(params.future_revno, 1);
+            assert_eq!(params.tree_delta.added.len(), 1);
+            c.lock().unwrap().push("pre_commit".to_string());
+            Ok(())
+        })
+        .unwrap();
+        let c = calls.clone();
+        let post_commit = install_post_commit_hook("breezyshim test post_commit", move |params| {
+            c.lock()
+                .unwrap()
+                .push(format!("post_commit {}", params.new_revno));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(pre_commit.name(), "pre_commit");
+        assert_eq!(pre_commit.label(), "breezyshim test pre_commit");
+
+        wt.build_commit()
+            .message("Add a")
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["start_commit", "pre_commit", "post_commit 1"]
+        );
+
+        std::mem::drop((start_commit, pre_commit, post_commit));
+        wt.build_commit()
+            .message("Empty")
+            .allow_pointless(true)
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap();
+        assert_eq!(calls.lock().unwrap().len(), 3);
+        std::mem::drop(env);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_branch_update_hooks() {
+        use crate::branch::Branch;
+        use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+        use crate::workingtree::WorkingTree;
+        use std::sync::Mutex;
+
+        let env = crate::testing::TestEnv::new();
+        std::fs::create_dir("a").unwrap();
+        let a =
+            create_standalone_workingtree(std::path::Path::new("a"), &ControlDirFormat::default())
+                .unwrap();
+        let commit = |wt: &crate::workingtree::GenericWorkingTree, message: &str| {
+            wt.build_commit()
+                .message(message)
+                .allow_pointless(true)
+                .reporter(&crate::commit::NullCommitReporter::new())
+                .commit()
+                .unwrap()
+        };
+        commit(&a, "One");
+        let b_url =
+            url::Url::from_directory_path(std::env::current_dir().unwrap().join("b")).unwrap();
+        let b = a
+            .controldir()
+            .sprout(b_url, None, Some(true), None, None)
+            .unwrap()
+            .open_workingtree()
+            .unwrap();
+        let r1 = b.branch().last_revision();
+        let r2 = commit(&a, "Two");
+        let a_url = a.branch().get_user_url();
+        let b_url = b.branch().get_user_url();
+
+        type Call = (
+            &'static str,
+            url::Url,
+            url::Url,
+            RevisionId,
+            RevisionId,
+            u32,
+        );
+        let calls: Arc<Mutex<Vec<Call>>> = Arc::new(Mutex::new(Vec::new()));
+        let c = calls.clone();
+        let post_pull = install_post_pull_hook("breezyshim test post_pull", move |result| {
+            assert!(result.master_branch.is_none());
+            assert!(result.local_branch.is_none());
+            c.lock().unwrap().push((
+                "post_pull",
+                result.source_branch.get_user_url(),
+                result.target_branch.get_user_url(),
+                result.old_revid.clone(),
+                result.new_revid.clone(),
+                result.new_revno,
+            ));
+            Ok(())
+        })
+        .unwrap();
+        let c = calls.clone();
+        let post_push = install_post_push_hook("breezyshim test post_push", move |result| {
+            c.lock().unwrap().push((
+                "post_push",
+                result.source_branch.get_user_url(),
+                result.target_branch.get_user_url(),
+                result.old_revid.clone(),
+                result.new_revid.clone(),
+                result.new_revno,
+            ));
+            Ok(())
+        })
+        .unwrap();
+        let c = calls.clone();
+        let post_change_branch_tip = install_post_change_branch_tip_hook(
+            "breezyshim test post_change_branch_tip",
+            move |params| {
+                assert_eq!(params.old_revno + 1, params.new_revno);
+                c.lock().unwrap().push((
+                    "post_change_branch_tip",
+                    params.branch.get_user_url(),
+                    params.branch.get_user_url(),
+                    params.old_revid.clone(),
+                    params.new_revid.clone(),
+                    params.new_revno,
+                ));
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        b.branch().pull(&a.branch(), None).unwrap();
+        assert_eq!(
+            std::mem::take(&mut *calls.lock().unwrap()),
+            vec![
+                (
+                    "post_change_branch_tip",
+                    b_url.clone(),
+                    b_url.clone(),
+                    r1.clone(),
+                    r2.clone(),
+                    2
+                ),
+                ("post_pull", a_url.clone(), b_url.clone(), r1, r2.clone(), 2),
+            ]
+        );
+
+        let r3 = commit(&b, "Three");
+        b.branch().push(&a.branch(), false, None, None).unwrap();
+        assert_eq!(
+            std::mem::take(&mut *calls.lock().unwrap()),
+            vec![
+                (
+                    "post_change_branch_tip",
+                    a_url.clone(),
+                    a_url.clone(),
+                    r2.clone(),
+                    r3.clone(),
+                    3
+                ),
+                ("post_push", b_url, a_url, r2, r3, 3),
+            ]
+        );
+
+        std::mem::drop((post_pull, post_push, post_change_branch_tip));
+        commit(&a, "Four");
+        b.branch().pull(&a.branch(), None).unwrap();
+        assert!(calls.lock().unwrap().is_empty());
+        std::mem::drop(env);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_merge_file_content_hook() {
+        use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+        use crate::tree::{MutableTree, Tree};
+        use crate::workingtree::WorkingTree;
+        use std::path::Path;
+
+        let env = crate::testing::TestEnv::new();
+        std::fs::create_dir("this").unwrap();
+        let wt =
+            create_standalone_workingtree(Path::new("this"), &ControlDirFormat::default()).unwrap();
+        let commit = |wt: &crate::workingtree::GenericWorkingTree, message: &str| {
+            wt.build_commit()
+                .message(message)
+                .reporter(&crate::commit::NullCommitReporter::new())
+                .commit()
+                .unwrap()
+        };
+        std::fs::write("this/a", b"base\n").unwrap();
+        wt.add(&[Path::new("a")]).unwrap();
+        commit(&wt, "Add a");
+        let other_url =
+            url::Url::from_directory_path(std::env::current_dir().unwrap().join("other")).unwrap();
+        let other = wt
+            .controldir()
+            .sprout(other_url, None, Some(true), None, None)
+            .unwrap()
+            .open_workingtree()
+            .unwrap();
+        std::fs::write("other/a", b"other\n").unwrap();
+        commit(&other, "Change a in other");
+        std::fs::write("this/a", b"this\n").unwrap();
+        commit(&wt, "Change a in this");
+
+        let merge_hooks = crate::merge::Merger::hooks();
+        let before = merge_hooks.get("merge_file_content").unwrap().len();
+        let guard = install_merge_file_content_hook("breezyshim test merger", |params| {
+            assert_eq!(params.this_path().as_deref(), Some(Path::new("a")));
+            assert_eq!(params.base_lines()?, vec![b"base\n".to_vec()]);
+            let mut lines = params.this_lines()?;
+            lines.extend(params.other_lines()?);
+            Ok(MergeFileResult::Success(lines))
+        })
+        .unwrap();
+        assert_eq!(guard.name(), "merge_file_content");
+        wt.merge_from_branch(&other.branch(), None).unwrap();
+        assert_eq!(std::fs::read("this/a").unwrap(), b"this\nother\n");
+        assert!(wt.conflicts().unwrap().is_empty());
+        std::mem::drop(guard);
+        assert_eq!(merge_hooks.get("merge_file_content").unwrap().len(), before);
+        std::mem::drop(env);
+    }
+
+    /// Call the hooks of a `MergeProposalBuilder` hook point the way
+    /// breezy's proposal builders do.
+    fn run_proposal_hooks(name: &str, params: &Bound<PyDict>) -> Vec<Py<PyAny>> {
+        HookDict::new("breezy.forge", "MergeProposalBuilder", "hooks")
+            .get(name)
+            .unwrap()
+            .into_iter()
+            .map(|hook| hook.0.call1(params.py(), (params,)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_merge_proposal_hooks() {
+        use crate::branch::Branch;
+        use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+        use crate::tree::PyTree;
+        use crate::workingtree::WorkingTree;
+
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(std::path::Path::new("."), &ControlDirFormat::default())
+                .unwrap();
+        let target_url = wt.branch().get_user_url();
+        let url = target_url.clone();
+        let body = install_merge_proposal_body_hook("breezyshim test body", move |params| {
+            assert_eq!(params.target_branch.get_user_url(), url);
+            Ok(Some(format!(
+                "{}Proposed body\n",
+                params.old_body.as_deref().unwrap_or("")
+            )))
+        })
+        .unwrap();
+        let url = target_url.clone();
+        let prerequisite =
+            install_get_prerequisite_hook("breezyshim test prerequisite", move |params| {
+                assert_eq!(params.target_branch.get_user_url(), url);
+                Ok(Some(params.target_branch.clone()))
+            })
+            .unwrap();
+
+        Python::attach(|py| {
+            let params = PyDict::new(py);
+            params.set_item("tree", wt.to_object(py)).unwrap();
+            params
+                .set_item("target_branch", wt.branch().to_object(py))
+                .unwrap();
+            params.set_item("old_body", "Existing\n").unwrap();
+            let bodies = run_proposal_hooks("merge_proposal_body", &params);
+            let body: String = bodies.last().unwrap().extract(py).unwrap();
+            assert!(body.ends_with("Proposed body\n"));
+
+            let prerequisites = run_proposal_hooks("get_prerequisite", &params);
+            let prerequisite: GenericBranch = prerequisites.last().unwrap().extract(py).unwrap();
+            assert_eq!(prerequisite.get_user_url(), target_url);
+        });
+
+        std::mem::drop((body, prerequisite));
+        Python::attach(|py| {
+            let params = PyDict::new(py);
+            params
+                .set_item("target_branch", wt.branch().to_object(py))
+                .unwrap();
+            params.set_item("old_body", py.None()).unwrap();
+            assert!(run_proposal_hooks("get_prerequisite", &params).is_empty());
+        });
+        std::mem::drop(env);
+    }
 }
@@ -53,4 +53,36 @@ impl InterTree {
                 .unwrap()
         })
     }
+
+    /// Compare the source and target trees, optionally restricted to specific files.
+    ///
+    /// # Arguments
+    ///
+    /// * `specific_files` - Only report changes to these paths (and their children)
+    /// * `want_unversioned` - Whether to include unversioned files in the delta
+    ///
+    /// # Returns
+    ///
+    /// A TreeDelta representing the differences between the source and target trees
+    pub fn compare_with(
+        &self,
+        specific_files: Option<&[&std::path::Path]>,
+        want_unversioned: bool,
+    ) -> Result<TreeDelta, crate::error::Error> {
+        Python::attach(|py| {
+            let kwargs = pyo3::types::PyDict::new(py);
+            if let Some(specific_files) = specific_files {
+                let files = specific_files
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect::<Vec<_>>();
+                kwargs.set_item("specific_files", files)?;
+            }
+            kwargs.set_item("want_unversioned", want_unversioned)?;
+            Ok(self
+                .0
+                .call_method(py, "compare", (), Some(&kwargs))?
+                .extract(py)?)
+        })
+    }
 }
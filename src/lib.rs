@@ -23,6 +23,7 @@
 // TODO: Fix large error enum variants by boxing large fields
 #![allow(clippy::result_large_err)]
 
+pub mod annotate;
 pub mod bazaar;
 pub mod branch;
 pub mod clean_tree;
@@ -56,8 +57,13 @@ pub mod knit;
 pub mod launchpad;
 pub mod location;
 pub mod lock;
+pub mod log;
+pub mod memorytree;
 pub mod mercurial;
 pub mod merge;
+pub mod merge_directive;
+#[cfg(feature = "async")]
+pub mod nonblocking;
 pub mod osutils;
 pub mod patches;
 pub mod plugin;
@@ -65,7 +71,9 @@ pub mod prelude;
 pub mod rename_map;
 pub mod repository;
 pub mod revisionid;
+pub mod revisionspec;
 pub mod search;
+pub mod shelf;
 pub mod status;
 pub mod subversion;
 pub mod tags;
@@ -208,7 +216,7 @@ fn do_init() -> std::result::Result<(), BreezyInitError> {
     }
 
     if major >= 4 {
-        log::warn!("Support for Breezy 4.0 is experimental and incomplete.");
+        ::log::warn!("Support for Breezy 4.0 is experimental and incomplete.");
     }
 
     init_git();
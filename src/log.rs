@@ -0,0 +1,794 @@
+//! Walking and formatting of revision history, like `brz log`.
+use crate::branch::Branch;
+use crate::delta::TreeDelta;
+use crate::error::Error;
+use crate::lock::Lock;
+use crate::repository::{GenericRepository, Repository, Revision};
+use crate::tree::TreeChange;
+use crate::RevisionId;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// The order in which log entries are produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogDirection {
+    /// Newest revisions first.
+    #[default]
+    Reverse,
+    /// Oldest revisions first. In a [`LogRequest`], merged revisions are
+    /// still listed after the revision that merged them; see
+    /// [`crate::branch::Branch::merge_sorted_revisions`] for the plain
+    /// reversal used there.
+    Forward,
+}
+
+/// A single revision in the log.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// The (possibly dotted) revision number, e.g. `"3"` or `"2.1.1"`.
+    pub revno: String,
+    /// The merge depth; `0` for mainline revisions.
+    pub depth: usize,
+    /// The revision itself.
+    pub revision: Revision,
+    /// The changes made in this revision, if requested.
+    pub delta: Option<TreeDelta>,
+    /// The tags pointing at this revision, sorted.
+    pub tags: Vec<String>,
+}
+
+/// Describes which revisions to log.
+///
+/// # Example
+///
+/// ```no_run
+/// use breezyshim::log::LogRequest;
+/// # let branch = breezyshim::branch::open(&"file:///tmp/branch".parse().unwrap()).unwrap();
+/// for entry in LogRequest::new().levels(0).limit(10).iter(branch.as_ref()).unwrap() {
+///     let entry = entry.unwrap();
+///     println!("{} {}", entry.revno, entry.revision.get_summary());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogRequest {
+    start_revision: Option<RevisionId>,
+    end_revision: Option<RevisionId>,
+    direction: LogDirection,
+    levels: usize,
+    specific_files: Vec<PathBuf>,
+    match_message: Option<regex::Regex>,
+    match_author: Option<regex::Regex>,
+    limit: Option<usize>,
+    delta: bool,
+}
+
+impl Default for LogRequest {
+    fn default() -> Self {
+        LogRequest {
+            start_revision: None,
+            end_revision: None,
+            direction: LogDirection::default(),
+            levels: 1,
+            specific_files: vec![],
+            match_message: None,
+            match_author: None,
+            limit: None,
+            delta: false,
+        }
+    }
+}
+
+impl LogRequest {
+    /// Create a request for the mainline history of a branch, newest first.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the oldest revision to include.
+    pub fn start_revision(mut self, revision_id: &RevisionId) -> Self {
+        self.start_revision = Some(revision_id.clone());
+        self
+    }
+
+    /// Set the newest revision to include; defaults to the branch tip.
+    pub fn end_revision(mut self, revision_id: &RevisionId) -> Self {
+        self.end_revision = Some(revision_id.clone());
+        self
+    }
+
+    /// Set the order in which entries are produced.
+    pub fn direction(mut self, direction: LogDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Set the number of levels of merged revisions to include.
+    ///
+    /// `1` (the default) only includes mainline revisions, `0` includes all
+    /// merged revisions.
+    pub fn levels(mut self, levels: usize) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    /// Only include revisions that changed the specified paths.
+    pub fn specific_files(mut self, files: &[&std::path::Path]) -> Self {
+        self.specific_files = files.iter().map(|p| p.to_path_buf()).collect();
+        self
+    }
+
+    /// Only include revisions whose message matches a regular expression.
+    pub fn match_message(mut self, regex: regex::Regex) -> Self {
+        self.match_message = Some(regex);
+        self
+    }
+
+    /// Only include revisions where one of the authors matches a regular
+    /// expression.
+    pub fn match_author(mut self, regex: regex::Regex) -> Self {
+        self.match_author = Some(regex);
+        self
+    }
+
+    /// Produce at most `limit` entries.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Include the changes made in each revision in the entries.
+    pub fn delta(mut self, delta: bool) -> Self {
+        self.delta = delta;
+        self
+    }
+
+    /// Walk the history of a branch.
+    ///
+    /// The branch is kept read-locked until the returned iterator is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `branch` - The branch to log
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the matching log entries
+    pub fn iter(&self, branch: &dyn Branch) -> Result<LogIterator, Error> {
+        let lock = branch.lock_read()?;
+        let repository = branch.repository();
+        let tip = branch.last_revision();
+        let candidates = if tip.is_null() {
+            vec![]
+        } else {
+            self.candidates(&repository, &tip)?
+        };
+        let tags = if candidates.is_empty() {
+            HashMap::new()
+        } else {
+            branch.tags()?.get_reverse_tag_dict()?
+        };
+        Ok(LogIterator {
+            request: self.clone(),
+            repository,
+            candidates: candidates.into(),
+            tags,
+            produced: 0,
+            _lock: lock,
+        })
+    }
+
+    fn candidates(
+        &self,
+        repository: &GenericRepository,
+        tip: &RevisionId,
+    ) -> Result<Vec<Candidate>, Error> {
+        let graph = repository.get_graph();
+        let mut parent_map = HashMap::new();
+        for item in graph.iter_ancestry(std::slice::from_ref(tip))? {
+            if let (revid, Some(parents)) = item? {
+                if !revid.is_null() {
+                    parent_map.insert(revid, parents);
+                }
+            }
+        }
+        let present: HashSet<RevisionId> = parent_map.keys().cloned().collect();
+        for parents in parent_map.values_mut() {
+            parents.retain(|p| present.contains(p));
+        }
+
+        let sorted = crate::tsort::merge_sort(&parent_map, tip)?;
+        let find = |revid: &RevisionId| {
+            sorted
+                .iter()
+                .position(|e| &e.node == revid)
+                .ok_or_else(|| Error::NoSuchRevision(revid.clone()))
+        };
+        let first = match &self.end_revision {
+            Some(revid) => find(revid)?,
+            None => 0,
+        };
+        let last = match &self.start_revision {
+            Some(revid) => find(revid)?,
+            None => sorted.len().saturating_sub(1),
+        };
+        if first > last {
+            return Ok(vec![]);
+        }
+        let candidates = sorted[first..=last]
+            .iter()
+            .filter(|e| self.levels == 0 || e.merge_depth < self.levels)
+            .map(|e| Candidate {
+                revno: e.revno_str(),
+                depth: e.merge_depth,
+                revision_id: e.node.clone(),
+            })
+            .collect();
+        Ok(match self.direction {
+            LogDirection::Reverse => candidates,
+            LogDirection::Forward => reverse_by_depth(candidates),
+        })
+    }
+
+    fn matches(&self, revision: &Revision) -> bool {
+        if let Some(regex) = &self.match_message {
+            if !regex.is_match(&revision.message) {
+                return false;
+            }
+        }
+        if let Some(regex) = &self.match_author {
+            if !revision
+                .get_apparent_authors()
+                .iter()
+                .any(|a| regex.is_match(a))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Candidate {
+    revno: String,
+    depth: usize,
+    revision_id: RevisionId,
+}
+
+/// Reverse merge-sorted revisions, keeping merged revisions after the
+/// revision that merged them.
+fn reverse_by_depth(candidates: Vec<Candidate>) -> Vec<Candidate> {
+    let depth = match candidates.iter().map(|c| c.depth).min() {
+        Some(depth) => depth,
+        None => return candidates,
+    };
+    let mut groups: Vec<Vec<Candidate>> = vec![];
+    for candidate in candidates {
+        match groups.last_mut() {
+            Some(group) if candidate.depth != depth => group.push(candidate),
+            _ => groups.push(vec![candidate]),
+        }
+    }
+    let mut ret = vec![];
+    for mut group in groups.into_iter().rev() {
+        let rest = group.split_off(1);
+        ret.extend(group);
+        ret.extend(reverse_by_depth(rest));
+    }
+    ret
+}
+
+/// Iterator over the entries selected by a [`LogRequest`].
+pub struct LogIterator {
+    request: LogRequest,
+    repository: GenericRepository,
+    candidates: VecDeque<Candidate>,
+    tags: HashMap<RevisionId, HashSet<String>>,
+    produced: usize,
+    _lock: Lock,
+}
+
+impl LogIterator {
+    fn entry(&self, candidate: Candidate) -> Result<Option<LogEntry>, Error> {
+        let revision = match self.repository.get_revision(&candidate.revision_id) {
+            Ok(revision) => revision,
+            Err(Error::NoSuchRevision(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if !self.request.matches(&revision) {
+            return Ok(None);
+        }
+        let delta = if self.request.delta || !self.request.specific_files.is_empty() {
+            let files = self
+                .request
+                .specific_files
+                .iter()
+                .map(|p| p.as_path())
+                .collect::<Vec<_>>();
+            let specific_files = if files.is_empty() {
+                None
+            } else {
+                Some(files.as_slice())
+            };
+            self.repository
+                .get_revision_deltas(std::slice::from_ref(&revision), specific_files)
+                .next()
+        } else {
+            None
+        };
+        if !self.request.specific_files.is_empty()
+            && !delta.as_ref().is_some_and(|d| d.has_changed())
+        {
+            return Ok(None);
+        }
+        let mut tags = self
+            .tags
+            .get(&candidate.revision_id)
+            .map(|tags| tags.iter().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        tags.sort();
+        Ok(Some(LogEntry {
+            revno: candidate.revno,
+            depth: candidate.depth,
+            revision,
+            delta: if self.request.delta { delta } else { None },
+            tags,
+        }))
+    }
+}
+
+impl Iterator for LogIterator {
+    type Item = Result<LogEntry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self
+            .request
+            .limit
+            .is_some_and(|limit| self.produced >= limit)
+        {
+            return None;
+        }
+        while let Some(candidate) = self.candidates.pop_front() {
+            match self.entry(candidate) {
+                Ok(Some(entry)) => {
+                    self.produced += 1;
+                    return Some(Ok(entry));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    self.candidates.clear();
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Formats log entries.
+pub trait LogFormatter {
+    /// Write a single log entry.
+    fn log_revision(&mut self, entry: &LogEntry) -> std::io::Result<()>;
+}
+
+fn merge_marker(entry: &LogEntry) -> &'static str {
+    if entry.revision.parent_ids.len() > 1 {
+        " [merge]"
+    } else {
+        ""
+    }
+}
+
+fn short_author(revision: &Revision) -> String {
+    let author = revision
+        .get_apparent_authors()
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    let (name, email) = crate::config::parse_username(&author);
+    if name.is_empty() {
+        email
+    } else {
+        name
+    }
+}
+
+fn change_path(change: &TreeChange, new: bool) -> String {
+    let (path, kind) = if new {
+        (change.path.1.as_ref(), change.kind.1.as_ref())
+    } else {
+        (change.path.0.as_ref(), change.kind.0.as_ref())
+    };
+    format!(
+        "{}{}",
+        path.map(|p| p.display().to_string()).unwrap_or_default(),
+        kind.map(|k| k.marker()).unwrap_or("")
+    )
+}
+
+fn write_delta(
+    w: &mut impl Write,
+    indent: &str,
+    delta: &TreeDelta,
+    short: bool,
+) -> std::io::Result<()> {
+    let sections: [(&str, &str, &[TreeChange]); 6] = [
+        ("removed", "D", &delta.removed),
+        ("added", "A", &delta.added),
+        ("renamed", "R", &delta.renamed),
+        ("copied", "C", &delta.copied),
+        ("kind changed", "K", &delta.kind_changed),
+        ("modified", "M", &delta.modified),
+    ];
+    for (title, letter, changes) in sections {
+        if changes.is_empty() {
+            continue;
+        }
+        if !short {
+            writeln!(w, "{}{}:", indent, title)?;
+        }
+        let prefix = if short {
+            format!("{}{}  ", indent, letter)
+        } else {
+            format!("{}  ", indent)
+        };
+        for change in changes {
+            let path = match letter {
+                "R" | "C" => format!(
+                    "{} => {}",
+                    change_path(change, false),
+                    change_path(change, true)
+                ),
+                "D" => change_path(change, false),
+                "K" => format!(
+                    "{} ({} => {})",
+                    change_path(change, true),
+                    change
+                        .kind
+                        .0
+                        .as_ref()
+                        .map(|k| k.to_string())
+                        .unwrap_or_default(),
+                    change
+                        .kind
+                        .1
+                        .as_ref()
+                        .map(|k| k.to_string())
+                        .unwrap_or_default()
+                ),
+                _ => change_path(change, true),
+            };
+            writeln!(w, "{}{}", prefix, path)?;
+        }
+    }
+    Ok(())
+}
+
+/// The detailed format used by `brz log --long`.
+pub struct LongLogFormatter<W: Write>(W);
+
+impl<W: Write> LongLogFormatter<W> {
+    /// Create a formatter writing to `w`.
+    pub fn new(w: W) -> Self {
+        LongLogFormatter(w)
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+impl<W: Write> LogFormatter for LongLogFormatter<W> {
+    fn log_revision(&mut self, entry: &LogEntry) -> std::io::Result<()> {
+        let indent = "    ".repeat(entry.depth);
+        let revision = &entry.revision;
+        let mut lines = vec![
+            "-".repeat(60),
+            format!("revno: {}{}", entry.revno, merge_marker(entry)),
+        ];
+        if !entry.tags.is_empty() {
+            lines.push(format!("tags: {}", entry.tags.join(", ")));
+        }
+        let authors = revision.get_apparent_authors();
+        if authors != [revision.committer.clone()] {
+            lines.push(format!("author: {}", authors.join(", ")));
+        }
+        lines.push(format!("committer: {}", revision.committer));
+        if let Some(nick) = revision.properties.get("branch-nick") {
+            lines.push(format!("branch nick: {}", nick));
+        }
+        lines.push(format!(
+            "timestamp: {}",
+            revision.datetime().format("%a %Y-%m-%d %H:%M:%S %z")
+        ));
+        lines.push("message:".to_string());
+        let message = revision.message.trim_end_matches(['\r', '\n']);
+        if message.is_empty() {
+            lines.push("  (no message)".to_string());
+        } else {
+            lines.extend(message.split('\n').map(|l| format!("  {}", l)));
+        }
+        for line in lines {
+            writeln!(self.0, "{}{}", indent, line)?;
+        }
+        if let Some(delta) = &entry.delta {
+            write_delta(&mut self.0, &indent, delta, false)?;
+        }
+        Ok(())
+    }
+}
+
+/// The compact format used by `brz log --short`.
+pub struct ShortLogFormatter<W: Write>(W);
+
+impl<W: Write> ShortLogFormatter<W> {
+    /// Create a formatter writing to `w`.
+    pub fn new(w: W) -> Self {
+        ShortLogFormatter(w)
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+impl<W: Write> LogFormatter for ShortLogFormatter<W> {
+    fn log_revision(&mut self, entry: &LogEntry) -> std::io::Result<()> {
+        let indent = "    ".repeat(entry.depth);
+        let revno_width = if entry.revno.contains('.') { 11 } else { 5 };
+        let offset = format!("{}{}", indent, " ".repeat(revno_width + 1));
+        let tags = if entry.tags.is_empty() {
+            String::new()
+        } else {
+            format!(" {{{}}}", entry.tags.join(", "))
+        };
+        writeln!(
+            self.0,
+            "{}{:>width$} {}\t{}{}{}",
+            indent,
+            entry.revno,
+            short_author(&entry.revision),
+            entry.revision.datetime().format("%Y-%m-%d"),
+            tags,
+            merge_marker(entry),
+            width = revno_width
+        )?;
+        let message = entry.revision.message.trim_end_matches(['\r', '\n']);
+        if message.is_empty() {
+            writeln!(self.0, "{}(no message)", offset)?;
+        } else {
+            for line in message.split('\n') {
+                writeln!(self.0, "{}{}", offset, line)?;
+            }
+        }
+        if let Some(delta) = &entry.delta {
+            write_delta(&mut self.0, &offset, delta, true)?;
+        }
+        writeln!(self.0)
+    }
+}
+
+/// The one-line-per-revision format used by `brz log --line`.
+pub struct LineLogFormatter<W: Write> {
+    w: W,
+    width: usize,
+}
+
+impl<W: Write> LineLogFormatter<W> {
+    /// Create a formatter writing to `w`, truncating lines to 79 characters.
+    pub fn new(w: W) -> Self {
+        LineLogFormatter { w, width: 79 }
+    }
+
+    /// Set the maximum line width.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+}
+
+fn truncate(s: &str, max_len: usize) -> String {
+    if s.chars().count() <= max_len {
+        s.to_string()
+    } else {
+        let mut ret: String = s.chars().take(max_len.saturating_sub(3)).collect();
+        ret.push_str("...");
+        ret
+    }
+}
+
+impl<W: Write> LogFormatter for LineLogFormatter<W> {
+    fn log_revision(&mut self, entry: &LogEntry) -> std::io::Result<()> {
+        let indent = "  ".repeat(entry.depth);
+        let revision = &entry.revision;
+        let mut out = vec![
+            format!("{}:", entry.revno),
+            truncate(&short_author(revision), 20),
+            revision.datetime().format("%Y-%m-%d").to_string(),
+        ];
+        if revision.parent_ids.len() > 1 {
+            out.push("[merge]".to_string());
+        }
+        if !entry.tags.is_empty() {
+            out.push(format!("{{{}}}", entry.tags.join(", ")));
+        }
+        out.push(revision.get_summary());
+        let line = truncate(&out.join(" "), self.width.saturating_sub(indent.len()));
+        writeln!(self.w, "{}{}", indent, line)
+    }
+}
+
+/// The GNU ChangeLog format.
+pub struct GnuChangelogLogFormatter<W: Write>(W);
+
+impl<W: Write> GnuChangelogLogFormatter<W> {
+    /// Create a formatter writing to `w`.
+    pub fn new(w: W) -> Self {
+        GnuChangelogLogFormatter(w)
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+impl<W: Write> LogFormatter for GnuChangelogLogFormatter<W> {
+    fn log_revision(&mut self, entry: &LogEntry) -> std::io::Result<()> {
+        let revision = &entry.revision;
+        let author = revision
+            .get_apparent_authors()
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+            .replace(" <", "  <");
+        writeln!(
+            self.0,
+            "{}  {}\n",
+            revision
+                .datetime()
+                .with_timezone(&chrono::Utc)
+                .format("%Y-%m-%d"),
+            author
+        )?;
+        if let Some(delta) = entry.delta.as_ref().filter(|d| d.has_changed()) {
+            for change in delta
+                .added
+                .iter()
+                .chain(&delta.removed)
+                .chain(&delta.modified)
+                .chain(&delta.kind_changed)
+            {
+                let path = change.path.1.as_ref().or(change.path.0.as_ref());
+                if let Some(path) = path {
+                    writeln!(self.0, "\t* {}:", path.display())?;
+                }
+            }
+            for change in delta.renamed.iter().chain(&delta.copied) {
+                writeln!(
+                    self.0,
+                    "\t* {}:\n\t* {}:",
+                    change_path(change, false),
+                    change_path(change, true)
+                )?;
+            }
+            writeln!(self.0)?;
+        }
+        let message = revision.message.trim_end_matches(['\r', '\n']);
+        if message.is_empty() {
+            writeln!(self.0, "\tNo commit message")?;
+        } else {
+            for line in message.split('\n') {
+                writeln!(self.0, "\t{}", line.trim_start())?;
+            }
+        }
+        writeln!(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+    use crate::tree::MutableTree;
+    use crate::workingtree::WorkingTree;
+    use serial_test::serial;
+    use std::path::Path;
+
+    #[test]
+    #[serial]
+    fn test_log() {
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(Path::new("."), &ControlDirFormat::default()).unwrap();
+        let branch = wt.branch();
+        assert_eq!(LogRequest::new().iter(&branch).unwrap().count(), 0);
+
+        std::fs::write("a", b"contents").unwrap();
+        wt.add(&[Path::new("a")]).unwrap();
+        let first = wt
+            .build_commit()
+            .message("Add a")
+            .committer("Joe Example <joe@example.com>")
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap();
+        std::fs::write("b", b"contents").unwrap();
+        wt.add(&[Path::new("b")]).unwrap();
+        wt.build_commit()
+            .message("Add b")
+            .committer("Jane Example <jane@example.com>")
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap();
+        branch.tags().unwrap().set_tag("v1", &first).unwrap();
+
+        let entries = LogRequest::new()
+            .iter(&branch)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.revno.as_str()).collect::<Vec<_>>(),
+            vec!["2", "1"]
+        );
+        assert_eq!(entries[1].tags, vec!["v1".to_string()]);
+
+        let entries = LogRequest::new()
+            .direction(LogDirection::Forward)
+            .specific_files(&[Path::new("b")])
+            .delta(true)
+            .iter(&branch)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].delta.as_ref().unwrap().added.len(), 1);
+
+        let entries = LogRequest::new()
+            .match_author(regex::Regex::new("joe@").unwrap())
+            .iter(&branch)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].revision.revision_id, first);
+
+        let mut formatter = LineLogFormatter::new(Vec::new());
+        formatter.log_revision(&entries[0]).unwrap();
+        let line = String::from_utf8(formatter.into_inner()).unwrap();
+        assert!(line.starts_with("1: Joe Example "), "{}", line);
+        assert!(line.ends_with(" {v1} Add a\n"), "{}", line);
+        std::mem::drop(env);
+    }
+
+    #[test]
+    fn test_reverse_by_depth() {
+        let candidate = |revno: &str, depth| Candidate {
+            revno: revno.to_string(),
+            depth,
+            revision_id: RevisionId::from(revno.as_bytes().to_vec()),
+        };
+        let reversed = reverse_by_depth(vec![
+            candidate("2", 0),
+            candidate("1.1.2", 1),
+            candidate("1.1.1", 1),
+            candidate("1", 0),
+        ]);
+        assert_eq!(
+            reversed
+                .iter()
+                .map(|c| c.revno.as_str())
+                .collect::<Vec<_>>(),
+            vec!["1", "2", "1.1.1", "1.1.2"]
+        );
+    }
+}
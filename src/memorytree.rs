@@ -0,0 +1,1080 @@
+//! A tree that lives entirely in Rust memory.
+//!
+//! [`MemoryTree`] implements [`Tree`] and [`MutableTree`] on top of Rust data
+//! structures, which makes it cheap to build fixtures in tests. Contents are
+//! never stored in Python, but locks and SHA-1 hashes are still created with
+//! Python helpers, and comparisons, transforms and archives are delegated to
+//! a Python copy of the tree. When an operation needs a Python tree, such as
+//! [`crate::diff::show_diff_trees`] or [`crate::export::export`], use
+//! [`MemoryTree::to_py_tree`].
+use crate::error::Error;
+use crate::lock::Lock;
+use crate::revisionid::{RevisionId, CURRENT_REVISION};
+use crate::tree::{
+    Conflict, InventoryDelta, Kind, MutableTree, PathContentSummary, PyTree, RevisionTree,
+    SearchRule, Tree, TreeChange, TreeEntry, TreeReference, WalkdirResult,
+};
+use pyo3::exceptions::{PyIsADirectoryError, PyNotADirectoryError, PyValueError};
+use pyo3::prelude::*;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Content {
+    Directory,
+    File { data: Vec<u8>, executable: bool },
+    Symlink { target: PathBuf },
+}
+
+impl Content {
+    fn kind(&self) -> Kind {
+        match self {
+            Content::Directory => Kind::Directory,
+            Content::File { .. } => Kind::File,
+            Content::Symlink { .. } => Kind::Symlink,
+        }
+    }
+
+    fn tree_entry(&self) -> TreeEntry {
+        match self {
+            Content::Directory => TreeEntry::Directory { revision: None },
+            Content::File { data, executable } => TreeEntry::File {
+                executable: *executable,
+                kind: Kind::File,
+                revision: None,
+                size: data.len() as u64,
+            },
+            Content::Symlink { target } => TreeEntry::Symlink {
+                revision: None,
+                symlink_target: target.to_string_lossy().to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    content: Content,
+    versioned: bool,
+    mtime: u64,
+}
+
+#[derive(Debug)]
+struct State {
+    entries: BTreeMap<PathBuf, Entry>,
+    parent_ids: Vec<RevisionId>,
+    basis: BTreeMap<PathBuf, Content>,
+}
+
+impl State {
+    fn get(&self, path: &Path) -> Result<&Entry, Error> {
+        self.entries
+            .get(path)
+            .ok_or_else(|| Error::NoSuchFile(path.to_path_buf()))
+    }
+
+    fn get_mut(&mut self, path: &Path) -> Result<&mut Entry, Error> {
+        self.entries
+            .get_mut(path)
+            .ok_or_else(|| Error::NoSuchFile(path.to_path_buf()))
+    }
+
+    fn file(&self, path: &Path) -> Result<(&[u8], bool), Error> {
+        match &self.get(path)?.content {
+            Content::File { data, executable } => Ok((data, *executable)),
+            Content::Directory => Err(PyIsADirectoryError::new_err(path_str(path)).into()),
+            Content::Symlink { .. } => Err(Error::Other(PyValueError::new_err(format!(
+                "{} is not a file",
+                path.display()
+            )))),
+        }
+    }
+
+    fn check_dir(&self, path: &Path) -> Result<(), Error> {
+        match self.get(path)?.content {
+            Content::Directory => Ok(()),
+            _ => Err(PyNotADirectoryError::new_err(path_str(path)).into()),
+        }
+    }
+
+    /// Check that `path` can be created: it must not exist and its parent
+    /// must be a directory.
+    fn check_new(&self, path: &Path) -> Result<(), Error> {
+        if self.entries.contains_key(path) {
+            return Err(Error::FileExists(path.to_path_buf(), None));
+        }
+        match path.parent() {
+            Some(parent) => self.check_dir(parent),
+            None => Err(Error::FileExists(path.to_path_buf(), None)),
+        }
+    }
+
+    fn children<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = (&'a PathBuf, &'a Entry)> {
+        self.entries
+            .iter()
+            .filter(move |(p, _)| p.parent() == Some(dir))
+    }
+
+    fn descendants<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = (&'a PathBuf, &'a Entry)> {
+        self.entries
+            .iter()
+            .filter(move |(p, _)| p.as_path() != dir && p.starts_with(dir))
+    }
+
+    /// Remove `path` and everything below it, returning the removed entries
+    /// keyed by their path relative to `path`.
+    fn take_subtree(&mut self, path: &Path) -> Result<BTreeMap<PathBuf, Entry>, Error> {
+        self.get(path)?;
+        let paths = self
+            .entries
+            .keys()
+            .filter(|p| p.starts_with(path))
+            .cloned()
+            .collect::<Vec<_>>();
+        Ok(paths
+            .into_iter()
+            .map(|p| {
+                let entry = self.entries.remove(&p).unwrap();
+                (p.strip_prefix(path).unwrap().to_path_buf(), entry)
+            })
+            .collect())
+    }
+
+    fn versioned(&self) -> BTreeMap<PathBuf, Content> {
+        self.entries
+            .iter()
+            .filter(|(_, e)| e.versioned)
+            .map(|(p, e)| (p.clone(), e.content.clone()))
+            .collect()
+    }
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn sha1_hex(data: &[u8]) -> Result<String, Error> {
+    Python::attach(|py| {
+        let hashlib = py.import("hashlib")?;
+        let sha = hashlib.call_method1("sha1", (pyo3::types::PyBytes::new(py, data),))?;
+        Ok(sha.call_method0("hexdigest")?.extract()?)
+    })
+}
+
+fn noop_lock() -> Result<Lock, Error> {
+    Python::attach(|py| {
+        let unlock = pyo3::types::PyCFunction::new_closure(
+            py,
+            None,
+            None,
+            |_args: &Bound<pyo3::types::PyTuple>,
+             _kwargs: Option<&Bound<pyo3::types::PyDict>>|
+             -> PyResult<()> { Ok(()) },
+        )?;
+        let result = py
+            .import("breezy.lock")?
+            .getattr("LogicalLockResult")?
+            .call1((unlock,))?;
+        Ok(Lock::from(result.unbind()))
+    })
+}
+
+static REVISION_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A mutable tree stored entirely in memory.
+///
+/// The tree starts out with only a root directory. Files written with
+/// [`MutableTree::put_file_bytes_non_atomic`] are unversioned until they are
+/// added, while [`MemoryTree::add_file`] and [`MemoryTree::add_symlink`] create
+/// versioned entries directly.
+///
+/// Locks taken on the tree are no-ops. Committing only records the new basis
+/// and parent ids; no revision is stored anywhere.
+#[derive(Debug)]
+pub struct MemoryTree(Mutex<State>);
+
+impl Default for MemoryTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryTree {
+    /// Create a new, empty tree.
+    pub fn new() -> Self {
+        let root = Entry {
+            content: Content::Directory,
+            versioned: true,
+            mtime: now(),
+        };
+        MemoryTree(Mutex::new(State {
+            entries: BTreeMap::from([(PathBuf::new(), root)]),
+            parent_ids: vec![],
+            basis: BTreeMap::from([(PathBuf::new(), Content::Directory)]),
+        }))
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap()
+    }
+
+    fn insert(&self, path: &Path, content: Content) -> Result<(), Error> {
+        let mut state = self.state();
+        state.check_new(path)?;
+        state.entries.insert(
+            path.to_path_buf(),
+            Entry {
+                content,
+                versioned: true,
+                mtime: now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Add a versioned file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the new file; its parent directory must exist
+    /// * `contents` - The contents of the file
+    /// * `executable` - Whether the file is executable
+    pub fn add_file(&self, path: &Path, contents: &[u8], executable: bool) -> Result<(), Error> {
+        self.insert(
+            path,
+            Content::File {
+                data: contents.to_vec(),
+                executable,
+            },
+        )
+    }
+
+    /// Add a versioned symbolic link.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the new symlink; its parent directory must exist
+    /// * `target` - The target of the symlink
+    pub fn add_symlink(&self, path: &Path, target: &Path) -> Result<(), Error> {
+        self.insert(
+            path,
+            Content::Symlink {
+                target: target.to_path_buf(),
+            },
+        )
+    }
+
+    /// Set or clear the executable bit of a file.
+    pub fn set_executable(&self, path: &Path, executable: bool) -> Result<(), Error> {
+        let mut state = self.state();
+        let entry = state.get_mut(path)?;
+        match &mut entry.content {
+            Content::File {
+                executable: current,
+                ..
+            } => {
+                *current = executable;
+                Ok(())
+            }
+            _ => Err(Error::Other(PyValueError::new_err(format!(
+                "{} is not a file",
+                path.display()
+            )))),
+        }
+    }
+
+    /// Build a Python memory tree with the same versioned contents.
+    ///
+    /// The Python tree is backed by a new branch in a memory transport, has
+    /// the same parent ids and is returned write-locked, since Python memory
+    /// trees discard their contents when they are unlocked. Unversioned files
+    /// are not copied.
+    pub fn to_py_tree(&self) -> Result<crate::tree::MemoryTree, Error> {
+        let branch = crate::controldir::create_branch_convenience_as_generic(
+            &"memory:///".parse().unwrap(),
+            Some(false),
+            &crate::controldir::ControlDirFormat::default(),
+        )?;
+        let tree = crate::tree::MemoryTree::from(&branch);
+        let state = self.state();
+        Python::attach(|py| -> Result<(), Error> {
+            let obj = tree.to_object(py);
+            obj.call_method0(py, "lock_write")?;
+            let kwargs = pyo3::types::PyDict::new(py);
+            kwargs.set_item("allow_leftmost_as_ghost", true)?;
+            obj.call_method(
+                py,
+                "set_parent_ids",
+                (state.parent_ids.clone(),),
+                Some(&kwargs),
+            )?;
+            for (path, entry) in state.entries.iter().filter(|(_, e)| e.versioned) {
+                if path.as_os_str().is_empty() {
+                    continue;
+                }
+                let path = path_str(path);
+                match &entry.content {
+                    Content::Directory => {
+                        obj.call_method1(py, "mkdir", (&path,))?;
+                        continue;
+                    }
+                    Content::File { data, .. } => {
+                        obj.call_method1(
+                            py,
+                            "put_file_bytes_non_atomic",
+                            (&path, pyo3::types::PyBytes::new(py, data)),
+                        )?;
+                    }
+                    Content::Symlink { .. } => {}
+                }
+                let kwargs = pyo3::types::PyDict::new(py);
+                kwargs.set_item("kinds", vec![entry.content.kind().to_string()])?;
+                obj.call_method(py, "add", (vec![&path],), Some(&kwargs))?;
+                let file_id = obj.call_method1(py, "path2id", (&path,))?;
+                let ie =
+                    obj.getattr(py, "_inventory")?
+                        .call_method1(py, "get_entry", (file_id,))?;
+                match &entry.content {
+                    Content::File { executable, .. } => {
+                        ie.setattr(py, "executable", *executable)?;
+                    }
+                    Content::Symlink { target } => {
+                        ie.setattr(py, "symlink_target", path_str(target))?;
+                    }
+                    Content::Directory => unreachable!(),
+                }
+            }
+            Ok(())
+        })?;
+        Ok(tree)
+    }
+}
+
+impl Tree for MemoryTree {
+    fn get_tag_dict(&self) -> Result<HashMap<String, RevisionId>, Error> {
+        Ok(HashMap::new())
+    }
+
+    fn get_file(&self, path: &Path) -> Result<Box<dyn std::io::Read>, Error> {
+        Ok(Box::new(std::io::Cursor::new(self.get_file_text(path)?)))
+    }
+
+    fn get_file_text(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        Ok(self.state().file(path)?.0.to_vec())
+    }
+
+    fn get_file_lines(&self, path: &Path) -> Result<Vec<Vec<u8>>, Error> {
+        Ok(self
+            .state()
+            .file(path)?
+            .0
+            .split_inclusive(|b| *b == b'\n')
+            .map(|l| l.to_vec())
+            .collect())
+    }
+
+    fn lock_read(&self) -> Result<Lock, Error> {
+        noop_lock()
+    }
+
+    fn has_filename(&self, path: &Path) -> bool {
+        self.state().entries.contains_key(path)
+    }
+
+    fn get_symlink_target(&self, path: &Path) -> Result<PathBuf, Error> {
+        match &self.state().get(path)?.content {
+            Content::Symlink { target } => Ok(target.clone()),
+            _ => Err(Error::Other(PyValueError::new_err(format!(
+                "{} is not a symlink",
+                path.display()
+            )))),
+        }
+    }
+
+    fn get_parent_ids(&self) -> Result<Vec<RevisionId>, Error> {
+        Ok(self.state().parent_ids.clone())
+    }
+
+    fn is_ignored(&self, _path: &Path) -> Option<String> {
+        None
+    }
+
+    fn kind(&self, path: &Path) -> Result<Kind, Error> {
+        Ok(self.state().get(path)?.content.kind())
+    }
+
+    fn is_versioned(&self, path: &Path) -> bool {
+        self.state()
+            .entries
+            .get(path)
+            .map(|e| e.versioned)
+            .unwrap_or(false)
+    }
+
+    fn iter_changes(
+        &self,
+        other: &dyn PyTree,
+        specific_files: Option<&[&Path]>,
+        want_unversioned: Option<bool>,
+        require_versioned: Option<bool>,
+    ) -> Result<Box<dyn Iterator<Item = Result<TreeChange, Error>>>, Error> {
+        self.to_py_tree()?
+            .iter_changes(other, specific_files, want_unversioned, require_versioned)
+    }
+
+    fn has_versioned_directories(&self) -> bool {
+        true
+    }
+
+    fn preview_transform(&self) -> Result<crate::transform::TreeTransform, Error> {
+        self.to_py_tree()?.preview_transform()
+    }
+
+    fn list_files(
+        &self,
+        include_root: Option<bool>,
+        from_dir: Option<&Path>,
+        recursive: Option<bool>,
+        _recurse_nested: Option<bool>,
+    ) -> Result<Box<dyn Iterator<Item = Result<(PathBuf, bool, Kind, TreeEntry), Error>>>, Error>
+    {
+        let state = self.state();
+        let root = from_dir.unwrap_or(Path::new(""));
+        state.check_dir(root)?;
+        let mut ret = vec![];
+        if include_root.unwrap_or(false) && from_dir.is_none() {
+            ret.push(Ok((
+                PathBuf::new(),
+                true,
+                Kind::Directory,
+                Content::Directory.tree_entry(),
+            )));
+        }
+        let entries: Box<dyn Iterator<Item = (&PathBuf, &Entry)>> = if recursive.unwrap_or(true) {
+            Box::new(state.descendants(root))
+        } else {
+            Box::new(state.children(root))
+        };
+        for (path, entry) in entries {
+            ret.push(Ok((
+                path.strip_prefix(root).unwrap().to_path_buf(),
+                entry.versioned,
+                entry.content.kind(),
+                entry.content.tree_entry(),
+            )));
+        }
+        Ok(Box::new(ret.into_iter()))
+    }
+
+    fn iter_child_entries(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = Result<(PathBuf, Kind, TreeEntry), Error>>>, Error> {
+        let state = self.state();
+        state.check_dir(path)?;
+        let ret = state
+            .children(path)
+            .filter(|(_, e)| e.versioned)
+            .map(|(p, e)| Ok((p.clone(), e.content.kind(), e.content.tree_entry())))
+            .collect::<Vec<_>>();
+        Ok(Box::new(ret.into_iter()))
+    }
+
+    fn get_file_size(&self, path: &Path) -> Result<u64, Error> {
+        Ok(self.state().file(path)?.0.len() as u64)
+    }
+
+    fn get_file_sha1(
+        &self,
+        path: &Path,
+        _stat_value: Option<&std::fs::Metadata>,
+    ) -> Result<String, Error> {
+        sha1_hex(&self.get_file_text(path)?)
+    }
+
+    fn get_file_mtime(&self, path: &Path) -> Result<u64, Error> {
+        Ok(self.state().get(path)?.mtime)
+    }
+
+    fn get_file_revision(&self, _path: &Path) -> Result<RevisionId, Error> {
+        Err(Error::UnsupportedOperation(
+            "get_file_revision".to_string(),
+            "MemoryTree".to_string(),
+        ))
+    }
+
+    fn is_executable(&self, path: &Path) -> Result<bool, Error> {
+        match self.state().get(path)?.content {
+            Content::File { executable, .. } => Ok(executable),
+            _ => Ok(false),
+        }
+    }
+
+    fn stored_kind(&self, path: &Path) -> Result<Kind, Error> {
+        self.kind(path)
+    }
+
+    fn supports_content_filtering(&self) -> bool {
+        false
+    }
+
+    fn supports_file_ids(&self) -> bool {
+        false
+    }
+
+    fn supports_rename_tracking(&self) -> bool {
+        false
+    }
+
+    fn supports_symlinks(&self) -> bool {
+        true
+    }
+
+    fn supports_tree_reference(&self) -> bool {
+        false
+    }
+
+    fn unknowns(&self) -> Result<Vec<PathBuf>, Error> {
+        Ok(self
+            .state()
+            .entries
+            .iter()
+            .filter(|(_, e)| !e.versioned)
+            .map(|(p, _)| p.clone())
+            .collect())
+    }
+
+    fn all_versioned_paths(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = Result<PathBuf, Error>>>, Error> {
+        let paths = self
+            .state()
+            .versioned()
+            .into_keys()
+            .map(Ok)
+            .collect::<Vec<_>>();
+        Ok(Box::new(paths.into_iter()))
+    }
+
+    fn conflicts(&self) -> Result<Vec<Conflict>, Error> {
+        Ok(vec![])
+    }
+
+    fn extras(&self) -> Result<Vec<PathBuf>, Error> {
+        self.unknowns()
+    }
+
+    fn filter_unversioned_files(&self, paths: &[&Path]) -> Result<Vec<PathBuf>, Error> {
+        Ok(paths
+            .iter()
+            .filter(|p| !self.is_versioned(p))
+            .map(|p| p.to_path_buf())
+            .collect())
+    }
+
+    fn walkdirs(
+        &self,
+        prefix: Option<&Path>,
+    ) -> Result<Box<dyn Iterator<Item = Result<WalkdirResult, Error>>>, Error> {
+        let state = self.state();
+        let prefix = prefix.unwrap_or(Path::new(""));
+        state.check_dir(prefix)?;
+        let ret = state
+            .descendants(prefix)
+            .map(|(p, e)| {
+                Ok(WalkdirResult {
+                    relpath: p.clone(),
+                    kind: e.content.kind(),
+                    stat: None,
+                    versioned: e.versioned,
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(Box::new(ret.into_iter()))
+    }
+
+    fn versionable_kind(&self, kind: &Kind) -> bool {
+        matches!(kind, Kind::File | Kind::Directory | Kind::Symlink)
+    }
+
+    fn path_content_summary(&self, path: &Path) -> Result<PathContentSummary, Error> {
+        let content = self.state().get(path)?.content.clone();
+        Ok(match content {
+            Content::Directory => PathContentSummary {
+                kind: Kind::Directory,
+                size: None,
+                executable: None,
+                sha1: None,
+                target: None,
+            },
+            Content::File { data, executable } => PathContentSummary {
+                kind: Kind::File,
+                size: Some(data.len() as u64),
+                executable: Some(executable),
+                sha1: Some(sha1_hex(&data)?),
+                target: None,
+            },
+            Content::Symlink { target } => PathContentSummary {
+                kind: Kind::Symlink,
+                size: None,
+                executable: None,
+                sha1: None,
+                target: Some(path_str(&target)),
+            },
+        })
+    }
+
+    fn iter_files_bytes(
+        &self,
+        paths: &[&Path],
+    ) -> Result<Box<dyn Iterator<Item = Result<(PathBuf, Vec<u8>), Error>>>, Error> {
+        let ret = paths
+            .iter()
+            .map(|p| Ok((p.to_path_buf(), self.get_file_text(p)?)))
+            .collect::<Vec<_>>();
+        Ok(Box::new(ret.into_iter()))
+    }
+
+    fn iter_entries_by_dir(
+        &self,
+        specific_files: Option<&[&Path]>,
+    ) -> Result<Box<dyn Iterator<Item = Result<(PathBuf, TreeEntry), Error>>>, Error> {
+        let state = self.state();
+        let in_scope = |path: &Path| {
+            specific_files
+                .map(|files| crate::osutils::is_inside_any(files, path))
+                .unwrap_or(true)
+        };
+        let mut ret = vec![];
+        if in_scope(Path::new("")) {
+            ret.push(Ok((PathBuf::new(), Content::Directory.tree_entry())));
+        }
+        // Like Breezy, list all children of a directory before descending
+        // into any of them.
+        let mut queue = VecDeque::from([PathBuf::new()]);
+        while let Some(dir) = queue.pop_front() {
+            for (path, entry) in state.children(&dir).filter(|(_, e)| e.versioned) {
+                if in_scope(path) {
+                    ret.push(Ok((path.clone(), entry.content.tree_entry())));
+                }
+                if entry.content == Content::Directory {
+                    queue.push_back(path.clone());
+                }
+            }
+        }
+        Ok(Box::new(ret.into_iter()))
+    }
+
+    fn get_file_verifier(
+        &self,
+        path: &Path,
+        stat_value: Option<&std::fs::Metadata>,
+    ) -> Result<(String, Vec<u8>), Error> {
+        Ok((
+            "SHA1".to_string(),
+            self.get_file_sha1(path, stat_value)?.into_bytes(),
+        ))
+    }
+
+    fn get_reference_revision(&self, _path: &Path) -> Result<RevisionId, Error> {
+        Err(Error::UnsupportedOperation(
+            "get_reference_revision".to_string(),
+            "MemoryTree".to_string(),
+        ))
+    }
+
+    fn archive(
+        &self,
+        format: &str,
+        name: &str,
+        root: Option<&str>,
+        subdir: Option<&Path>,
+        force_mtime: Option<f64>,
+        recurse_nested: bool,
+    ) -> Result<Box<dyn Iterator<Item = Result<Vec<u8>, Error>>>, Error> {
+        self.to_py_tree()?
+            .archive(format, name, root, subdir, force_mtime, recurse_nested)
+    }
+
+    fn annotate_iter(
+        &self,
+        path: &Path,
+        default_revision: Option<&RevisionId>,
+    ) -> Result<Box<dyn Iterator<Item = Result<(RevisionId, Vec<u8>), Error>>>, Error> {
+        let revision = default_revision
+            .cloned()
+            .unwrap_or_else(|| RevisionId::from(CURRENT_REVISION));
+        let lines = self.get_file_lines(path)?;
+        Ok(Box::new(
+            lines.into_iter().map(move |l| Ok((revision.clone(), l))),
+        ))
+    }
+
+    fn is_special_path(&self, _path: &Path) -> bool {
+        false
+    }
+
+    fn iter_search_rules(
+        &self,
+        _paths: &[&Path],
+    ) -> Result<Box<dyn Iterator<Item = Result<SearchRule, Error>>>, Error> {
+        Ok(Box::new(std::iter::empty()))
+    }
+}
+
+impl MutableTree for MemoryTree {
+    fn add(&self, files: &[&Path]) -> Result<(), Error> {
+        let mut state = self.state();
+        for f in files {
+            state.get(f)?;
+            for ancestor in f.ancestors() {
+                state.get_mut(ancestor)?.versioned = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn lock_write(&self) -> Result<Lock, Error> {
+        noop_lock()
+    }
+
+    fn put_file_bytes_non_atomic(&self, path: &Path, data: &[u8]) -> Result<(), Error> {
+        let mut state = self.state();
+        match state.entries.get_mut(path) {
+            Some(Entry {
+                content: Content::File { data: current, .. },
+                mtime,
+                ..
+            }) => {
+                *current = data.to_vec();
+                *mtime = now();
+                Ok(())
+            }
+            Some(Entry {
+                content: Content::Directory,
+                ..
+            }) => Err(PyIsADirectoryError::new_err(path_str(path)).into()),
+            Some(entry) => {
+                entry.content = Content::File {
+                    data: data.to_vec(),
+                    executable: false,
+                };
+                entry.mtime = now();
+                Ok(())
+            }
+            None => {
+                state.check_new(path)?;
+                state.entries.insert(
+                    path.to_path_buf(),
+                    Entry {
+                        content: Content::File {
+                            data: data.to_vec(),
+                            executable: false,
+                        },
+                        versioned: false,
+                        mtime: now(),
+                    },
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn has_changes(&self) -> Result<bool, Error> {
+        let state = self.state();
+        Ok(state.parent_ids.len() > 1 || state.versioned() != state.basis)
+    }
+
+    fn mkdir(&self, path: &Path) -> Result<(), Error> {
+        self.insert(path, Content::Directory)
+    }
+
+    fn remove(&self, files: &[&Path]) -> Result<(), Error> {
+        let mut state = self.state();
+        for f in files {
+            if f.as_os_str().is_empty() {
+                return Err(Error::Other(PyValueError::new_err(
+                    "can not remove the tree root",
+                )));
+            }
+            state.get(f)?;
+            for (path, entry) in state.entries.iter_mut() {
+                if path.starts_with(f) {
+                    entry.versioned = false;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_reference(&self, _reference: &TreeReference) -> Result<(), Error> {
+        Err(Error::UnsupportedOperation(
+            "add_reference".to_string(),
+            "MemoryTree".to_string(),
+        ))
+    }
+
+    fn copy_one(&self, from_path: &Path, to_path: &Path) -> Result<(), Error> {
+        let mut state = self.state();
+        state.check_new(to_path)?;
+        let copies = state
+            .entries
+            .iter()
+            .filter(|(p, _)| p.starts_with(from_path))
+            .map(|(p, e)| {
+                (
+                    to_path.join(p.strip_prefix(from_path).unwrap()),
+                    Entry {
+                        versioned: true,
+                        ..e.clone()
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        if copies.is_empty() {
+            return Err(Error::NoSuchFile(from_path.to_path_buf()));
+        }
+        state.entries.extend(copies);
+        Ok(())
+    }
+
+    fn last_revision(&self) -> Result<RevisionId, Error> {
+        Ok(self
+            .state()
+            .parent_ids
+            .first()
+            .cloned()
+            .unwrap_or_else(RevisionId::null))
+    }
+
+    fn lock_tree_write(&self) -> Result<Lock, Error> {
+        noop_lock()
+    }
+
+    fn set_parent_ids(&self, parent_ids: &[RevisionId]) -> Result<(), Error> {
+        self.state().parent_ids = parent_ids.to_vec();
+        Ok(())
+    }
+
+    fn set_parent_trees(&self, parent_trees: &[(RevisionId, RevisionTree)]) -> Result<(), Error> {
+        self.set_parent_ids(
+            &parent_trees
+                .iter()
+                .map(|(revid, _)| revid.clone())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn apply_inventory_delta(&self, delta: Vec<InventoryDelta>) -> Result<(), Error> {
+        let mut state = self.state();
+        let delta = delta
+            .into_iter()
+            .filter(|d| {
+                d.old_path.as_deref() != Some(Path::new(""))
+                    || d.new_path.as_deref() != Some(Path::new(""))
+            })
+            .collect::<Vec<_>>();
+
+        // Take out old entries deepest first, so that entries inside a
+        // renamed directory are handled before the directory itself.
+        let mut removals = delta
+            .iter()
+            .filter_map(|d| d.old_path.as_ref().map(|p| (p, &d.file_id)))
+            .collect::<Vec<_>>();
+        removals.sort_by_key(|(p, _)| std::cmp::Reverse(p.components().count()));
+        let mut old = HashMap::new();
+        for (path, file_id) in removals {
+            old.insert(file_id.clone(), state.take_subtree(path)?);
+        }
+
+        let mut additions = delta
+            .iter()
+            .filter_map(|d| d.new_path.as_ref().map(|p| (p, d)))
+            .collect::<Vec<_>>();
+        additions.sort_by(|a, b| a.0.cmp(b.0));
+        for (path, d) in additions {
+            let mut subtree = old.remove(&d.file_id).unwrap_or_default();
+            let previous = subtree.remove(Path::new(""));
+            let content = match &d.entry {
+                Some(TreeEntry::File { executable, .. }) => Content::File {
+                    data: match previous.as_ref().map(|e| &e.content) {
+                        Some(Content::File { data, .. }) => data.clone(),
+                        _ => vec![],
+                    },
+                    executable: *executable,
+                },
+                Some(TreeEntry::Directory { .. }) => Content::Directory,
+                Some(TreeEntry::Symlink { symlink_target, .. }) => Content::Symlink {
+                    target: PathBuf::from(symlink_target),
+                },
+                Some(TreeEntry::TreeReference { .. }) => {
+                    return Err(Error::UnsupportedOperation(
+                        "apply_inventory_delta".to_string(),
+                        "MemoryTree".to_string(),
+                    ));
+                }
+                None => {
+                    return Err(Error::Other(PyValueError::new_err(format!(
+                        "no entry for new path {}",
+                        path.display()
+                    ))));
+                }
+            };
+            state.check_new(path)?;
+            state.entries.insert(
+                path.clone(),
+                Entry {
+                    content,
+                    versioned: true,
+                    mtime: previous.map(|e| e.mtime).unwrap_or_else(now),
+                },
+            );
+            for (relpath, entry) in subtree {
+                state.entries.insert(path.join(relpath), entry);
+            }
+        }
+        Ok(())
+    }
+
+    fn commit(
+        &self,
+        _message: &str,
+        committer: Option<&str>,
+        timestamp: Option<f64>,
+        allow_pointless: Option<bool>,
+        specific_files: Option<&[&Path]>,
+    ) -> Result<RevisionId, Error> {
+        let mut state = self.state();
+        let in_scope = |p: &Path| match specific_files {
+            Some(files) => crate::osutils::is_inside_any(files, p),
+            None => true,
+        };
+        let mut basis = state
+            .basis
+            .iter()
+            .filter(|(p, _)| !in_scope(p))
+            .map(|(p, c)| (p.clone(), c.clone()))
+            .collect::<BTreeMap<_, _>>();
+        basis.extend(state.versioned().into_iter().filter(|(p, _)| in_scope(p)));
+        if allow_pointless == Some(false) && state.parent_ids.len() <= 1 && basis == state.basis {
+            return Err(Error::PointlessCommit);
+        }
+        let timestamp = timestamp.map(|t| t as u64).unwrap_or_else(now);
+        let revid = RevisionId::from(
+            format!(
+                "{}-{}-{}",
+                committer
+                    .map(|c| crate::config::parse_username(c).1)
+                    .filter(|e| !e.is_empty())
+                    .unwrap_or_else(|| "memorytree".to_string()),
+                timestamp,
+                REVISION_COUNTER.fetch_add(1, Ordering::SeqCst)
+            )
+            .into_bytes(),
+        );
+        state.basis = basis;
+        state.parent_ids = vec![revid.clone()];
+        Ok(revid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files() {
+        let tree = MemoryTree::new();
+        tree.mkdir(Path::new("dir")).unwrap();
+        tree.add_file(Path::new("dir/a"), b"a\nb\n", true).unwrap();
+        tree.add_symlink(Path::new("link"), Path::new("dir/a"))
+            .unwrap();
+        tree.put_file_bytes_non_atomic(Path::new("unknown"), b"x")
+            .unwrap();
+
+        assert_eq!(tree.kind(Path::new("dir")).unwrap(), Kind::Directory);
+        assert_eq!(
+            tree.get_file_lines(Path::new("dir/a")).unwrap(),
+            vec![b"a\n".to_vec(), b"b\n".to_vec()]
+        );
+        assert!(tree.is_executable(Path::new("dir/a")).unwrap());
+        assert_eq!(
+            tree.get_symlink_target(Path::new("link")).unwrap(),
+            PathBuf::from("dir/a")
+        );
+        assert_eq!(tree.unknowns().unwrap(), vec![PathBuf::from("unknown")]);
+        assert!(matches!(
+            tree.get_file_text(Path::new("missing")),
+            Err(Error::NoSuchFile(_))
+        ));
+        assert!(matches!(
+            tree.add_file(Path::new("missing/b"), b"", false),
+            Err(Error::NoSuchFile(_))
+        ));
+
+        let paths = tree
+            .list_files(None, None, None, None)
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("dir"),
+                PathBuf::from("dir/a"),
+                PathBuf::from("link"),
+                PathBuf::from("unknown"),
+            ]
+        );
+        let paths = tree
+            .iter_entries_by_dir(None)
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::new(),
+                PathBuf::from("dir"),
+                PathBuf::from("link"),
+                PathBuf::from("dir/a"),
+            ]
+        );
+
+        tree.remove(&[Path::new("dir")]).unwrap();
+        assert!(!tree.is_versioned(Path::new("dir/a")));
+        assert_eq!(tree.get_file_text(Path::new("dir/a")).unwrap(), b"a\nb\n");
+    }
+
+    #[test]
+    fn test_commit() {
+        let tree = MemoryTree::new();
+        assert!(!tree.has_changes().unwrap());
+        tree.put_file_bytes_non_atomic(Path::new("a"), b"contents")
+            .unwrap();
+        assert!(!tree.has_changes().unwrap());
+        tree.add(&[Path::new("a")]).unwrap();
+        assert!(tree.has_changes().unwrap());
+
+        let revid = tree
+            .commit("Add a", Some("Joe <joe@example.com>"), None, None, None)
+            .unwrap();
+        assert!(revid.as_str().starts_with("joe@example.com-"));
+        assert_eq!(tree.get_parent_ids().unwrap(), vec![revid.clone()]);
+        assert_eq!(tree.last_revision().unwrap(), revid);
+        assert!(!tree.has_changes().unwrap());
+        assert!(matches!(
+            tree.commit("Nothing", None, None, Some(false), None),
+            Err(Error::PointlessCommit)
+        ));
+    }
+}
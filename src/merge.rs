@@ -1,13 +1,17 @@
 //! Tree merging.
-use crate::branch::PyBranch;
+use crate::branch::{Branch, PyBranch};
 use crate::graph::Graph;
-use crate::hooks::HookDict;
+use crate::hooks::{Hook, HookDict};
+use crate::repository::Repository;
 use crate::transform::TreeTransform;
-use crate::tree::PyTree;
+use crate::tree::{Conflict, Kind, PyTree, Tree, TreeChange};
+use crate::workingtree::PyWorkingTree;
 use crate::RevisionId;
 use pyo3::import_exception;
 use pyo3::prelude::*;
-use pyo3::types::PyDict;
+use pyo3::types::{PyBytes, PyDict, PyList};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 import_exception!(breezy.errors, UnrelatedBranches);
 
@@ -44,6 +48,21 @@ pub enum MergeType {
     /// This is the standard merge algorithm that uses a common base revision
     /// and the two branches to be merged.
     Merge3,
+
+    /// Weave merge algorithm.
+    ///
+    /// Uses the annotated history of each file to decide which lines were
+    /// added or removed on each side, which avoids some spurious conflicts.
+    Weave,
+
+    /// Least common ancestor merge algorithm.
+    ///
+    /// Like the weave merge, but uses the least common ancestors of the two
+    /// sides rather than a single base when building the merge plan.
+    Lca,
+
+    /// Merge using the external `diff3` program.
+    Diff3,
 }
 
 impl From<Py<PyAny>> for Merger {
@@ -164,11 +183,70 @@ impl Merger {
             let m = py.import("breezy.merge").unwrap();
             let merge_type = match merge_type {
                 MergeType::Merge3 => m.getattr("Merge3Merger").unwrap(),
+                MergeType::Weave => m.getattr("WeaveMerger").unwrap(),
+                MergeType::Lca => m.getattr("LCAMerger").unwrap(),
+                MergeType::Diff3 => m.getattr("Diff3Merger").unwrap(),
             };
             self.0.setattr(py, "merge_type", merge_type).unwrap();
         })
     }
 
+    /// Set whether to reduce conflicts by reprocessing conflicted regions.
+    ///
+    /// Not all merge types support this; merging fails if it is enabled for
+    /// one that does not.
+    ///
+    /// # Arguments
+    ///
+    /// * `reprocess` - Whether to reprocess conflicts
+    pub fn set_reprocess(&mut self, reprocess: bool) {
+        Python::attach(|py| {
+            self.0.setattr(py, "reprocess", reprocess).unwrap();
+        })
+    }
+
+    /// Set whether to include the base text in conflict markers.
+    ///
+    /// Not all merge types support this; merging fails if it is enabled for
+    /// one that does not.
+    ///
+    /// # Arguments
+    ///
+    /// * `show_base` - Whether to show the base text in conflicts
+    pub fn set_show_base(&mut self, show_base: bool) {
+        Python::attach(|py| {
+            self.0.setattr(py, "show_base", show_base).unwrap();
+        })
+    }
+
+    /// Perform the merge into the tree.
+    ///
+    /// # Returns
+    ///
+    /// The number of conflicts the merge produced
+    pub fn do_merge(&self) -> Result<usize, crate::error::Error> {
+        Python::attach(|py| Ok(self.0.call_method0(py, "do_merge")?.extract(py)?))
+    }
+
+    /// Record the merged revision as a pending merge in the tree.
+    ///
+    /// Nothing is recorded unless the base is an ancestor of both sides, so
+    /// this is a no-op for cherry-picks.
+    pub fn set_pending(&self) -> Result<(), crate::error::Error> {
+        Python::attach(|py| {
+            self.0.call_method0(py, "set_pending")?;
+            Ok(())
+        })
+    }
+
+    /// The hooks that are called during merge operations.
+    ///
+    /// Per-file mergers are registered on the `merge_file_content` hook; see
+    /// [`install_per_file_merger`].
+    pub fn hooks() -> &'static HookDict {
+        &MERGE_HOOKS
+    }
+
     /// Create a submerger to execute the merge.
     ///
     /// # Returns
@@ -243,7 +321,444 @@ impl Submerger {
     }
 }
 
+/// The outcome of merging the contents of a single file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeFileResult {
+    /// The merger does not handle this file; the next merger is tried.
+    NotApplicable,
+    /// The file was merged cleanly into these lines.
+    Success(Vec<Vec<u8>>),
+    /// The file was merged into these lines, but with a text conflict.
+    Conflicted(Vec<Vec<u8>>),
+    /// The file should be removed.
+    Delete,
+}
+
+/// A file that is being merged, as passed to a [`PerFileMerger`].
+pub struct MergeFileParams(Py<PyAny>);
+
+impl MergeFileParams {
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        Python::attach(|py| self.0.getattr(py, name).unwrap().extract(py).unwrap())
+    }
+
+    fn lines(&self, name: &str) -> Result<Vec<Vec<u8>>, crate::error::Error> {
+        Python::attach(|py| Ok(self.0.getattr(py, name)?.extract(py)?))
+    }
+
+    /// The path of the file in the base tree, if it exists there.
+    pub fn base_path(&self) -> Option<PathBuf> {
+        self.path("base_path")
+    }
+
+    /// The path of the file in the tree being merged, if it exists there.
+    pub fn other_path(&self) -> Option<PathBuf> {
+        self.path("other_path")
+    }
+
+    /// The path of the file in the tree being merged into, if it exists there.
+    pub fn this_path(&self) -> Option<PathBuf> {
+        self.path("this_path")
+    }
+
+    /// The kind of the file in the tree being merged into.
+    pub fn this_kind(&self) -> Option<Kind> {
+        Python::attach(|py| {
+            self.0
+                .getattr(py, "this_kind")
+                .unwrap()
+                .extract(py)
+                .unwrap()
+        })
+    }
+
+    /// The kind of the file in the tree being merged.
+    pub fn other_kind(&self) -> Option<Kind> {
+        Python::attach(|py| {
+            self.0
+                .getattr(py, "other_kind")
+                .unwrap()
+                .extract(py)
+                .unwrap()
+        })
+    }
+
+    /// Whether both sides of the merge are regular files.
+    pub fn is_file_merge(&self) -> bool {
+        self.this_kind() == Some(Kind::File) && self.other_kind() == Some(Kind::File)
+    }
+
+    /// The lines of the file in the base tree.
+    pub fn base_lines(&self) -> Result<Vec<Vec<u8>>, crate::error::Error> {
+        self.lines("base_lines")
+    }
+
+    /// The lines of the file in the tree being merged.
+    pub fn other_lines(&self) -> Result<Vec<Vec<u8>>, crate::error::Error> {
+        self.lines("other_lines")
+    }
+
+    /// The lines of the file in the tree being merged into.
+    pub fn this_lines(&self) -> Result<Vec<Vec<u8>>, crate::error::Error> {
+        self.lines("this_lines")
+    }
+}
+
+/// A merger for the contents of individual files.
+///
+/// Per-file mergers are consulted in turn for every file that changed on
+/// both sides of a merge, before the merge algorithm's own text merge.
+/// Conflicts they report show up in [`crate::tree::Tree::conflicts`] like any
+/// other text conflict.
+pub trait PerFileMerger: Send + Sync {
+    /// Merge the contents of a file.
+    ///
+    /// Return [`MergeFileResult::NotApplicable`] to leave the file to the
+    /// next merger.
+    fn merge_contents(
+        &self,
+        params: &MergeFileParams,
+    ) -> Result<MergeFileResult, crate::error::Error>;
+}
+
+impl<F> PerFileMerger for F
+where
+    F: Fn(&MergeFileParams) -> Result<MergeFileResult, crate::error::Error> + Send + Sync,
+{
+    fn merge_contents(
+        &self,
+        params: &MergeFileParams,
+    ) -> Result<MergeFileResult, crate::error::Error> {
+        self(params)
+    }
+}
+
+#[pyclass]
+struct PerFileMergerWrapper(Arc<dyn PerFileMerger>);
+
+#[pymethods]
+impl PerFileMergerWrapper {
+    /// Called by the merge with the `Merge3Merger` (or similar) instance.
+    fn __call__(slf: Py<Self>, _merger: Py<PyAny>) -> Py<Self> {
+        slf
+    }
+
+    fn merge_contents(&self, py: Python, params: Py<PyAny>) -> PyResult<(String, Py<PyAny>)> {
+        let result = self.0.merge_contents(&MergeFileParams(params))?;
+        let lines = |lines: Vec<Vec<u8>>| -> PyResult<Py<PyAny>> {
+            Ok(PyList::new(py, lines.iter().map(|l| PyBytes::new(py, l)))?
+                .into_any()
+                .unbind())
+        };
+        Ok(match result {
+            MergeFileResult::NotApplicable => ("not_applicable".to_string(), py.None()),
+            MergeFileResult::Success(l) => ("success".to_string(), lines(l)?),
+            MergeFileResult::Conflicted(l) => ("conflicted".to_string(), lines(l)?),
+            MergeFileResult::Delete => ("delete".to_string(), py.None()),
+        })
+    }
+}
+
+/// Install a per-file merger for all subsequent merges.
+///
+/// # Arguments
+///
+/// * `label` - A label identifying the merger, used to uninstall it
+/// * `merger` - The merger to install
+///
+/// # Returns
+///
+/// `Ok(())` on success, or an error if the operation fails
+pub fn install_per_file_merger(
+    label: &str,
+    merger: Arc<dyn PerFileMerger>,
+) -> Result<(), crate::error::Error> {
+    let wrapper = Python::attach(|py| Py::new(py, PerFileMergerWrapper(merger)))?;
+    Merger::hooks().install_named_hook("merge_file_content", Hook::from(wrapper.into_any()), label)
+}
+
+/// Uninstall a per-file merger installed with [`install_per_file_merger`].
+///
+/// # Arguments
+///
+/// * `label` - The label the merger was installed with
+pub fn uninstall_per_file_merger(label: &str) -> Result<(), crate::error::Error> {
+    Merger::hooks().uninstall_named_hook("merge_file_content", label)
+}
+
+/// The result of merging changes into a working tree.
+#[derive(Debug)]
+pub struct MergeResult {
+    /// The conflicts introduced by the merge.
+    ///
+    /// Conflicts that were already present in the tree are not included.
+    pub conflicts: Vec<Conflict>,
+    /// The paths that the merge changed.
+    ///
+    /// This includes paths that already had uncommitted changes.
+    pub modified: Vec<PathBuf>,
+}
+
+fn merge_into(
+    wt: &dyn PyWorkingTree,
+    other_branch: &dyn PyBranch,
+    base: &RevisionId,
+    other: &RevisionId,
+    merge_type: MergeType,
+) -> Result<MergeResult, crate::error::Error> {
+    let _lock = wt.lock_tree_write()?;
+    let before = wt.conflicts()?;
+    let mut merger = Python::attach(|py| -> Result<Merger, crate::error::Error> {
+        let m = py.import("breezy.merge")?;
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("other", other.clone())?;
+        kwargs.set_item("base", base.clone())?;
+        kwargs.set_item("other_branch", other_branch.to_object(py))?;
+        kwargs.set_item("tree_branch", wt.branch().to_object(py))?;
+        Ok(Merger(
+            m.getattr("Merger")?
+                .call_method("from_revision_ids", (wt.to_object(py),), Some(&kwargs))?
+                .unbind(),
+        ))
+    })?;
+    merger.set_merge_type(merge_type);
+    // The preview is built against the working tree itself, so its changes
+    // are exactly what the merge is about to do to the tree.
+    let preview = merger.make_merger()?.make_preview_transform()?;
+    let changes = preview
+        .get_preview_tree()?
+        .iter_changes(wt, None, None, None)
+        .and_then(|changes| changes.collect::<Result<Vec<TreeChange>, _>>());
+    preview.finalize()?;
+    let modified = changes?
+        .into_iter()
+        .filter_map(|c| c.path.1.or(c.path.0))
+        .collect();
+    merger.do_merge()?;
+    merger.set_pending()?;
+    let conflicts = wt
+        .conflicts()?
+        .into_iter()
+        .filter(|c| !before.contains(c))
+        .collect();
+    Ok(MergeResult {
+        conflicts,
+        modified,
+    })
+}
+
+/// Merge the changes between two revisions into a working tree.
+///
+/// Only the changes made after `from` up to and including `to` are merged.
+/// `to` is only recorded as a pending merge if `from` is an ancestor of both
+/// the tree and `to`, in which case this is an ordinary merge.
+///
+/// # Arguments
+///
+/// * `wt` - The working tree to merge into
+/// * `branch` - The branch containing the revisions
+/// * `from` - The revision before the first change to merge
+/// * `to` - The last revision to merge
+/// * `merge_type` - The merge algorithm to use
+///
+/// # Returns
+///
+/// The conflicts and changed paths in the tree
+pub fn cherry_pick(
+    wt: &dyn PyWorkingTree,
+    branch: &dyn PyBranch,
+    from: &RevisionId,
+    to: &RevisionId,
+    merge_type: MergeType,
+) -> Result<MergeResult, crate::error::Error> {
+    merge_into(wt, branch, from, to, merge_type)
+}
+
+/// Back out the changes made by a revision in a working tree.
+///
+/// The revision must be in the repository of the tree's branch. No pending
+/// merge is recorded.
+///
+/// # Arguments
+///
+/// * `wt` - The working tree to change
+/// * `revision` - The revision whose changes to back out
+/// * `merge_type` - The merge algorithm to use
+///
+/// # Returns
+///
+/// The conflicts and changed paths in the tree
+pub fn reverse_revision(
+    wt: &dyn PyWorkingTree,
+    revision: &RevisionId,
+    merge_type: MergeType,
+) -> Result<MergeResult, crate::error::Error> {
+    let branch = wt.branch();
+    let parent = branch
+        .repository()
+        .get_revision(revision)?
+        .parent_ids
+        .into_iter()
+        .next()
+        .unwrap_or_else(RevisionId::null);
+    merge_into(wt, &branch, revision, &parent, merge_type)
+}
+
 lazy_static::lazy_static! {
     /// Hooks that are called during merge operations.
     pub static ref MERGE_HOOKS: HookDict = HookDict::new("breezy.merge", "Merger", "hooks");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch::Branch;
+    use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+    use crate::repository::Repository;
+    use crate::tree::{MutableTree, Tree};
+    use crate::workingtree::WorkingTree;
+    use serial_test::serial;
+    use std::path::Path;
+
+    struct UnionMerger;
+
+    impl PerFileMerger for UnionMerger {
+        fn merge_contents(
+            &self,
+            params: &MergeFileParams,
+        ) -> Result<MergeFileResult, crate::error::Error> {
+            if params.this_path().as_deref() != Some(Path::new("a")) {
+                return Ok(MergeFileResult::NotApplicable);
+            }
+            let mut lines = params.this_lines()?;
+            lines.extend(params.other_lines()?);
+            Ok(MergeFileResult::Conflicted(lines))
+        }
+    }
+
+    fn commit(wt: &crate::workingtree::GenericWorkingTree, message: &str) -> RevisionId {
+        wt.build_commit()
+            .message(message)
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn test_per_file_merger() {
+        let env = crate::testing::TestEnv::new();
+        std::fs::create_dir("this").unwrap();
+        let wt =
+            create_standalone_workingtree(Path::new("this"), &ControlDirFormat::default()).unwrap();
+        std::fs::write("this/a", b"base\n").unwrap();
+        wt.add(&[Path::new("a")]).unwrap();
+        commit(&wt, "Add a");
+
+        let other_url =
+            url::Url::from_directory_path(std::env::current_dir().unwrap().join("other")).unwrap();
+        let other = wt
+            .controldir()
+            .sprout(other_url, None, Some(true), None, None)
+            .unwrap()
+            .open_workingtree()
+            .unwrap();
+        std::fs::write("other/a", b"other\n").unwrap();
+        let other_revid = commit(&other, "Change a in other");
+        std::fs::write("this/a", b"this\n").unwrap();
+        commit(&wt, "Change a in this");
+
+        install_per_file_merger("breezyshim test merger", Arc::new(UnionMerger)).unwrap();
+        let branch = wt.branch();
+        let mut merger = Merger::new(&branch, &wt, &branch.repository().get_graph());
+        merger
+            .set_other_revision(&other_revid, &other.branch())
+            .unwrap();
+        merger.find_base().unwrap();
+        merger.set_merge_type(MergeType::Merge3);
+        let conflicts = merger.do_merge();
+        uninstall_per_file_merger("breezyshim test merger").unwrap();
+
+        assert_eq!(conflicts.unwrap(), 1);
+        assert_eq!(std::fs::read("this/a").unwrap(), b"this\nother\n");
+        let conflicts = wt.conflicts().unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, Path::new("a"));
+        std::mem::drop(env);
+    }
+
+    #[test]
+    #[serial]
+    fn test_cherry_pick() {
+        let env = crate::testing::TestEnv::new();
+        std::fs::create_dir("this").unwrap();
+        let wt =
+            create_standalone_workingtree(Path::new("this"), &ControlDirFormat::default()).unwrap();
+        commit(&wt, "Initial");
+
+        let other_url =
+            url::Url::from_directory_path(std::env::current_dir().unwrap().join("other")).unwrap();
+        let other = wt
+            .controldir()
+            .sprout(other_url, None, Some(true), None, None)
+            .unwrap()
+            .open_workingtree()
+            .unwrap();
+        std::fs::write("other/b", b"b\n").unwrap();
+        other.add(&[Path::new("b")]).unwrap();
+        let from = commit(&other, "Add b");
+        std::fs::write("other/c", b"c\n").unwrap();
+        other.add(&[Path::new("c")]).unwrap();
+        let to = commit(&other, "Add c");
+
+        let result = cherry_pick(&wt, &other.branch(), &from, &to, MergeType::Merge3).unwrap();
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.modified, vec![PathBuf::from("c")]);
+        assert!(!wt.has_filename(Path::new("b")));
+        assert_eq!(wt.get_parent_ids().unwrap().len(), 1);
+        std::mem::drop(env);
+    }
+
+    #[test]
+    #[serial]
+    fn test_reverse_revision() {
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(Path::new("."), &ControlDirFormat::default()).unwrap();
+        std::fs::write("a", b"one\n").unwrap();
+        wt.add(&[Path::new("a")]).unwrap();
+        commit(&wt, "Add a");
+        std::fs::write("a", b"two\n").unwrap();
+        let revid = commit(&wt, "Change a");
+
+        let result = reverse_revision(&wt, &revid, MergeType::Merge3).unwrap();
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.modified, vec![PathBuf::from("a")]);
+        assert_eq!(std::fs::read("a").unwrap(), b"one\n");
+        assert_eq!(wt.get_parent_ids().unwrap(), vec![revid]);
+        std::mem::drop(env);
+    }
+
+    #[test]
+    #[serial]
+    fn test_reverse_revision_dirty_tree() {
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(Path::new("."), &ControlDirFormat::default()).unwrap();
+        std::fs::write("a", b"one\ntwo\nthree\n").unwrap();
+        std::fs::write("b", b"b\n").unwrap();
+        wt.add(&[Path::new("a"), Path::new("b")]).unwrap();
+        commit(&wt, "Add a and b");
+        std::fs::write("a", b"ONE\ntwo\nthree\n").unwrap();
+        let revid = commit(&wt, "Change a");
+        std::fs::write("a", b"ONE\ntwo\nTHREE\n").unwrap();
+        std::fs::write("b", b"B\n").unwrap();
+
+        let result = reverse_revision(&wt, &revid, MergeType::Merge3).unwrap();
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.modified, vec![PathBuf::from("a")]);
+        assert_eq!(std::fs::read("a").unwrap(), b"one\ntwo\nTHREE\n");
+        assert_eq!(std::fs::read("b").unwrap(), b"B\n");
+        std::mem::drop(env);
+    }
+}
@@ -0,0 +1,418 @@
+//! Merge directives.
+//!
+//! A merge directive is a request to merge a revision into a target branch,
+//! as produced by `brz send`. It can carry a diff, a bundle with the
+//! revisions themselves, or neither, and may be signed with GPG.
+use crate::branch::PyBranch;
+use crate::error::Error;
+use crate::gpg::GPGStrategy;
+use crate::merge::Merger;
+use crate::repository::PyRepository;
+use crate::workingtree::PyWorkingTree;
+use crate::RevisionId;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+/// What a merge directive carries besides the revision id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PatchType {
+    /// A bundle with the revisions, plus a preview diff.
+    #[default]
+    Bundle,
+    /// Only a diff against the base revision.
+    Diff,
+    /// Nothing; the revisions have to be fetched from the source branch.
+    None,
+}
+
+/// A merge directive.
+pub struct MergeDirective(Py<PyAny>);
+
+impl From<Py<PyAny>> for MergeDirective {
+    fn from(obj: Py<PyAny>) -> Self {
+        MergeDirective(obj)
+    }
+}
+
+impl<'py> IntoPyObject<'py> for MergeDirective {
+    type Target = PyAny;
+    type Output = Bound<'py, Self::Target>;
+    type Error = std::convert::Infallible;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        Ok(self.0.into_bound(py))
+    }
+}
+
+impl Clone for MergeDirective {
+    fn clone(&self) -> Self {
+        Python::attach(|py| MergeDirective(self.0.clone_ref(py)))
+    }
+}
+
+impl std::fmt::Debug for MergeDirective {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MergeDirective")
+            .field("revision_id", &self.revision_id())
+            .field("target_branch", &self.target_branch())
+            .finish()
+    }
+}
+
+impl MergeDirective {
+    /// Parse a serialized merge directive.
+    ///
+    /// Clear-signed directives are accepted as well; the signature is not
+    /// checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The serialized directive
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        Python::attach(|py| {
+            let m = py.import("breezy.merge_directive")?;
+            let lines = data
+                .split_inclusive(|b| *b == b'\n')
+                .map(|l| PyBytes::new(py, l))
+                .collect::<Vec<_>>();
+            let directive = m
+                .getattr("MergeDirective")?
+                .call_method1("from_lines", (lines,))?;
+            Ok(MergeDirective(directive.unbind()))
+        })
+    }
+
+    /// Serialize the directive.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Python::attach(|py| {
+            let lines = self
+                .0
+                .call_method0(py, "to_lines")?
+                .extract::<Vec<Vec<u8>>>(py)?;
+            Ok(lines.concat())
+        })
+    }
+
+    /// Serialize the directive and clear-sign it.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy` - The GPG strategy to sign with
+    pub fn to_signed_bytes(&self, strategy: &GPGStrategy) -> Result<Vec<u8>, Error> {
+        let data = self.to_bytes()?;
+        Python::attach(|py| {
+            let mode = py.import("breezy.gpg")?.getattr("MODE_CLEAR")?;
+            Ok(strategy
+                .to_object()
+                .call_method1(py, "sign", (PyBytes::new(py, &data), mode))?
+                .extract(py)?)
+        })
+    }
+
+    /// The revision to merge.
+    pub fn revision_id(&self) -> RevisionId {
+        Python::attach(|py| {
+            self.0
+                .getattr(py, "revision_id")
+                .unwrap()
+                .extract(py)
+                .unwrap()
+        })
+    }
+
+    /// The revision the patch and bundle are based on, if known.
+    pub fn base_revision_id(&self) -> Option<RevisionId> {
+        Python::attach(|py| {
+            self.0
+                .bind(py)
+                .getattr("base_revision_id")
+                .ok()
+                .and_then(|r| r.extract().ok())
+        })
+    }
+
+    /// The URL of the branch to merge into.
+    pub fn target_branch(&self) -> String {
+        Python::attach(|py| {
+            self.0
+                .getattr(py, "target_branch")
+                .unwrap()
+                .extract(py)
+                .unwrap()
+        })
+    }
+
+    /// The public URL of the branch the revision comes from, if any.
+    pub fn source_branch(&self) -> Option<String> {
+        Python::attach(|py| {
+            self.0
+                .getattr(py, "source_branch")
+                .unwrap()
+                .extract(py)
+                .unwrap()
+        })
+    }
+
+    /// The message accompanying the directive, if any.
+    pub fn message(&self) -> Option<String> {
+        Python::attach(|py| self.0.getattr(py, "message").unwrap().extract(py).unwrap())
+    }
+
+    /// The time the directive was created, in seconds since the epoch, and
+    /// its timezone offset in seconds.
+    pub fn time(&self) -> (f64, i32) {
+        Python::attach(|py| {
+            (
+                self.0.getattr(py, "time").unwrap().extract(py).unwrap(),
+                self.0.getattr(py, "timezone").unwrap().extract(py).unwrap(),
+            )
+        })
+    }
+
+    /// What the directive carries.
+    pub fn patch_type(&self) -> PatchType {
+        Python::attach(|py| {
+            match self
+                .0
+                .getattr(py, "patch_type")
+                .unwrap()
+                .extract::<Option<String>>(py)
+                .unwrap()
+                .as_deref()
+            {
+                Some("bundle") => PatchType::Bundle,
+                Some("diff") => PatchType::Diff,
+                _ => PatchType::None,
+            }
+        })
+    }
+
+    /// The diff carried by the directive, if any.
+    pub fn patch(&self) -> Option<Vec<u8>> {
+        Python::attach(|py| self.0.getattr(py, "patch").unwrap().extract(py).unwrap())
+    }
+
+    /// Install the revisions from the directive into a repository.
+    ///
+    /// If the directive does not carry a bundle, the revisions are fetched
+    /// from the source branch.
+    ///
+    /// # Arguments
+    ///
+    /// * `repository` - The repository to install the revisions into
+    ///
+    /// # Returns
+    ///
+    /// The revision to merge
+    pub fn install_revisions<R: PyRepository>(&self, repository: &R) -> Result<RevisionId, Error> {
+        Python::attach(|py| {
+            Ok(self
+                .0
+                .call_method1(py, "install_revisions", (repository.to_object(py),))?
+                .extract(py)?)
+        })
+    }
+
+    /// Create a merger that merges the directive into a working tree.
+    ///
+    /// The revisions are installed into the repository of the tree's branch
+    /// first.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The working tree to merge into
+    pub fn merger(&self, tree: &dyn PyWorkingTree) -> Result<Merger, Error> {
+        Python::attach(|py| {
+            let m = py.import("breezy.merge")?;
+            let (merger, _verified): (Py<PyAny>, Py<PyAny>) = m
+                .getattr("Merger")?
+                .call_method1("from_mergeable", (tree.to_object(py), self.0.clone_ref(py)))?
+                .extract()?;
+            Ok(Merger::from(merger))
+        })
+    }
+}
+
+/// Builder for a [`MergeDirective`].
+pub struct MergeDirectiveBuilder<'a> {
+    branch: &'a dyn PyBranch,
+    target: url::Url,
+    patch_type: PatchType,
+    revision_id: Option<RevisionId>,
+    base_revision_id: Option<RevisionId>,
+    local_target_branch: Option<&'a dyn PyBranch>,
+    public_branch: Option<url::Url>,
+    message: Option<String>,
+    time: Option<(f64, i32)>,
+}
+
+impl<'a> MergeDirectiveBuilder<'a> {
+    /// Start building a directive to merge `branch` into `target`.
+    ///
+    /// # Arguments
+    ///
+    /// * `branch` - The branch with the revision to merge
+    /// * `target` - The URL of the branch to merge into
+    pub fn new(branch: &'a dyn PyBranch, target: &url::Url) -> Self {
+        MergeDirectiveBuilder {
+            branch,
+            target: target.clone(),
+            patch_type: PatchType::default(),
+            revision_id: None,
+            base_revision_id: None,
+            local_target_branch: None,
+            public_branch: None,
+            message: None,
+            time: None,
+        }
+    }
+
+    /// Set what the directive carries. Defaults to [`PatchType::Bundle`].
+    pub fn patch_type(mut self, patch_type: PatchType) -> Self {
+        self.patch_type = patch_type;
+        self
+    }
+
+    /// Set the revision to merge. Defaults to the tip of the branch.
+    pub fn revision_id(mut self, revision_id: &RevisionId) -> Self {
+        self.revision_id = Some(revision_id.clone());
+        self
+    }
+
+    /// Set the revision to base the patch on. Defaults to the last revision
+    /// the branch and the target have in common.
+    pub fn base_revision_id(mut self, base_revision_id: &RevisionId) -> Self {
+        self.base_revision_id = Some(base_revision_id.clone());
+        self
+    }
+
+    /// Use a local copy of the target branch to find the base revision,
+    /// rather than opening the target URL.
+    pub fn local_target_branch(mut self, branch: &'a dyn PyBranch) -> Self {
+        self.local_target_branch = Some(branch);
+        self
+    }
+
+    /// Set the public URL of the branch, so the revisions can be fetched
+    /// from it.
+    pub fn public_branch(mut self, public_branch: &url::Url) -> Self {
+        self.public_branch = Some(public_branch.clone());
+        self
+    }
+
+    /// Set the message accompanying the directive.
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    /// Set the creation time, in seconds since the epoch, and the timezone
+    /// offset in seconds. Defaults to the current local time.
+    pub fn time(mut self, time: f64, timezone: i32) -> Self {
+        self.time = Some((time, timezone));
+        self
+    }
+
+    /// Create the directive.
+    pub fn build(self) -> Result<MergeDirective, Error> {
+        Python::attach(|py| {
+            let branch = self.branch.to_object(py);
+            let revision_id = match self.revision_id {
+                Some(revision_id) => revision_id.into_pyobject(py).unwrap().unbind(),
+                None => branch.call_method0(py, "last_revision")?,
+            };
+            let (time, timezone) = match self.time {
+                Some(time) => time,
+                None => (
+                    py.import("time")?.call_method0("time")?.extract()?,
+                    py.import("breezy.osutils")?
+                        .call_method0("local_time_offset")?
+                        .extract()?,
+                ),
+            };
+            let kwargs = PyDict::new(py);
+            kwargs.set_item(
+                "include_patch",
+                matches!(self.patch_type, PatchType::Bundle | PatchType::Diff),
+            )?;
+            kwargs.set_item("include_bundle", self.patch_type == PatchType::Bundle)?;
+            if let Some(local_target_branch) = self.local_target_branch {
+                kwargs.set_item("local_target_branch", local_target_branch.to_object(py))?;
+            }
+            if let Some(public_branch) = self.public_branch {
+                kwargs.set_item("public_branch", public_branch.to_string())?;
+            }
+            kwargs.set_item("message", self.message)?;
+            kwargs.set_item("base_revision_id", self.base_revision_id)?;
+            let m = py.import("breezy.merge_directive")?;
+            let directive = m.getattr("MergeDirective2")?.call_method(
+                "from_objects",
+                (
+                    branch.getattr(py, "repository")?,
+                    revision_id,
+                    time,
+                    timezone,
+                    self.target.to_string(),
+                ),
+                Some(&kwargs),
+            )?;
+            Ok(MergeDirective(directive.unbind()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch::Branch;
+    use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+    use crate::repository::Repository;
+    use crate::tree::MutableTree;
+    use crate::workingtree::WorkingTree;
+    use serial_test::serial;
+    use std::path::Path;
+
+    #[test]
+    #[serial]
+    fn test_roundtrip() {
+        let env = crate::testing::TestEnv::new();
+        let target_url =
+            url::Url::from_directory_path(std::env::current_dir().unwrap().join("target")).unwrap();
+        let target = crate::controldir::create_branch_convenience_as_generic(
+            &target_url,
+            Some(false),
+            &ControlDirFormat::default(),
+        )
+        .unwrap();
+        std::fs::create_dir("source").unwrap();
+        let wt = create_standalone_workingtree(Path::new("source"), &ControlDirFormat::default())
+            .unwrap();
+        std::fs::write("source/a", b"contents\n").unwrap();
+        wt.add(&[Path::new("a")]).unwrap();
+        let revid = wt
+            .build_commit()
+            .message("Add a")
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap();
+
+        let branch = wt.branch();
+        let directive = MergeDirectiveBuilder::new(&branch, &target_url)
+            .message("Please merge")
+            .build()
+            .unwrap();
+        assert_eq!(directive.revision_id(), revid);
+        assert_eq!(directive.patch_type(), PatchType::Bundle);
+
+        let parsed = MergeDirective::from_bytes(&directive.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed.revision_id(), revid);
+        assert_eq!(parsed.message().as_deref(), Some("Please merge"));
+        assert!(parsed.patch().unwrap().starts_with(b"=== added file"));
+
+        let repository = target.repository();
+        assert!(!repository.has_revision(&revid).unwrap());
+        assert_eq!(parsed.install_revisions(&repository).unwrap(), revid);
+        assert!(repository.has_revision(&revid).unwrap());
+        std::mem::drop(env);
+    }
+}
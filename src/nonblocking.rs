@@ -0,0 +1,286 @@
+//! Async versions of expensive operations.
+//!
+//! Breezy calls block the calling thread while they hold the GIL or wait for
+//! the network. The functions in this module run them on a small pool of
+//! worker threads instead, and return a [`Task`] that can be awaited from any
+//! async runtime.
+//!
+//! Dropping a [`Task`] before its operation has started cancels it. An
+//! operation that has already started runs to completion, but its result is
+//! discarded. If the operation panics, the task resolves to
+//! [`Error::Cancelled`].
+//!
+//! This module is only available with the `async` feature.
+use crate::branch::{Branch, GenericBranch};
+use crate::controldir::{ControlDir, GenericControlDir, PyControlDir};
+use crate::error::Error;
+use crate::forge::{MergeProposal, ProposalBuilder};
+use crate::repository::{GenericRepository, Repository};
+use crate::RevisionId;
+use futures_channel::oneshot;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::task::{Context, Poll};
+
+/// Number of worker threads used to run operations.
+const WORKER_COUNT: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static POOL: OnceLock<Mutex<mpsc::Sender<Job>>> = OnceLock::new();
+
+fn pool() -> &'static Mutex<mpsc::Sender<Job>> {
+    POOL.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..WORKER_COUNT {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("breezyshim-worker-{}", i))
+                .spawn(move || loop {
+                    let job = match receiver.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    // A panicking job drops its result sender, which the
+                    // task reports as a cancellation.
+                    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+                })
+                .expect("failed to spawn worker thread");
+        }
+        Mutex::new(sender)
+    })
+}
+
+/// An operation running on the worker pool.
+///
+/// Resolves to the result of the operation, or [`Error::Cancelled`] if the
+/// operation did not complete.
+pub struct Task<T> {
+    receiver: oneshot::Receiver<Result<T, Error>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<T> Future for Task<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.receiver).poll(cx) {
+            Poll::Ready(Ok(result)) => Poll::Ready(result),
+            Poll::Ready(Err(oneshot::Canceled)) => Poll::Ready(Err(Error::Cancelled)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Run an arbitrary operation on the worker pool.
+///
+/// # Arguments
+///
+/// * `f` - The operation to run
+///
+/// # Returns
+///
+/// A task that resolves to the result of the operation
+pub fn spawn<T, F>(f: F) -> Task<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let job_cancelled = cancelled.clone();
+    let job: Job = Box::new(move || {
+        if job_cancelled.load(Ordering::SeqCst) {
+            return;
+        }
+        let _ = sender.send(f());
+    });
+    // If the pool has shut down the job is dropped, which cancels the task.
+    let _ = pool().lock().unwrap().send(job);
+    Task {
+        receiver,
+        cancelled,
+    }
+}
+
+/// Push a branch to a remote branch.
+///
+/// # Arguments
+///
+/// * `branch` - The branch to push
+/// * `remote_branch` - The branch to push to
+/// * `overwrite` - Whether to overwrite the remote branch if it has diverged
+/// * `stop_revision` - The revision to push, or `None` for the tip
+/// * `tag_selector` - A function that selects which tags to push, or `None` to push all tags
+pub fn push(
+    branch: GenericBranch,
+    remote_branch: GenericBranch,
+    overwrite: bool,
+    stop_revision: Option<RevisionId>,
+    tag_selector: Option<Box<dyn Fn(String) -> bool + Send>>,
+) -> Task<()> {
+    spawn(move || {
+        branch.push(
+            &remote_branch,
+            overwrite,
+            stop_revision.as_ref(),
+            tag_selector.map(|f| f as Box<dyn Fn(String) -> bool>),
+        )
+    })
+}
+
+/// Pull from a source branch into a branch.
+///
+/// # Arguments
+///
+/// * `branch` - The branch to pull into
+/// * `source_branch` - The branch to pull from
+/// * `overwrite` - Whether to overwrite the branch if it has diverged from the source
+pub fn pull(
+    branch: GenericBranch,
+    source_branch: GenericBranch,
+    overwrite: Option<bool>,
+) -> Task<()> {
+    spawn(move || branch.pull(&source_branch, overwrite))
+}
+
+/// Fetch revisions from another repository.
+///
+/// # Arguments
+///
+/// * `repository` - The repository to fetch into
+/// * `source` - The repository to fetch from
+/// * `stop_revision` - The revision to fetch, or `None` for all revisions
+pub fn fetch(
+    repository: GenericRepository,
+    source: GenericRepository,
+    stop_revision: Option<RevisionId>,
+) -> Task<()> {
+    spawn(move || repository.fetch(&source, stop_revision.as_ref()))
+}
+
+fn as_generic(
+    controldir: Box<
+        dyn ControlDir<
+            Branch = GenericBranch,
+            Repository = GenericRepository,
+            WorkingTree = crate::workingtree::GenericWorkingTree,
+        >,
+    >,
+) -> Result<GenericControlDir, Error> {
+    match controldir.as_any().downcast_ref::<GenericControlDir>() {
+        Some(cd) => Ok(GenericControlDir::new(pyo3::Python::attach(|py| {
+            cd.to_object(py)
+        }))),
+        None => Err(Error::Other(pyo3::exceptions::PyTypeError::new_err(
+            "ControlDir must be a PyControlDir",
+        ))),
+    }
+}
+
+/// Open a control directory.
+///
+/// # Arguments
+///
+/// * `url` - The location of the control directory
+pub fn open(url: url::Url) -> Task<GenericControlDir> {
+    spawn(move || as_generic(crate::controldir::open(&url, None)?))
+}
+
+/// Create a copy of a control directory at a new location.
+///
+/// # Arguments
+///
+/// * `controldir` - The control directory to copy
+/// * `target` - The location to create the copy at
+/// * `source_branch` - The branch to copy, or `None` for the branch in `controldir`
+/// * `create_tree_if_local` - Whether to create a working tree if the target is local
+/// * `stacked` - Whether the new branch should be stacked on the source
+/// * `revision_id` - The revision to copy, or `None` for the tip
+pub fn sprout(
+    controldir: GenericControlDir,
+    target: url::Url,
+    source_branch: Option<GenericBranch>,
+    create_tree_if_local: Option<bool>,
+    stacked: Option<bool>,
+    revision_id: Option<RevisionId>,
+) -> Task<GenericControlDir> {
+    spawn(move || {
+        as_generic(
+            controldir.sprout(
+                target,
+                source_branch
+                    .as_ref()
+                    .map(|b| b as &dyn crate::branch::PyBranch),
+                create_tree_if_local,
+                stacked,
+                revision_id.as_ref(),
+            )?,
+        )
+    })
+}
+
+/// Create a merge proposal.
+///
+/// # Arguments
+///
+/// * `builder` - The proposal to create, as returned by [`crate::forge::Forge::get_proposer`]
+pub fn create_proposal(builder: ProposalBuilder) -> Task<MergeProposal> {
+    spawn(move || builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::{Wake, Waker};
+
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_spawn() {
+        assert_eq!(block_on(spawn(|| Ok(42))).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_panic_is_cancelled() {
+        let task: Task<()> = spawn(|| panic!("boom"));
+        assert!(matches!(block_on(task), Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn test_open_missing() {
+        let td = tempfile::tempdir().unwrap();
+        let url = url::Url::from_directory_path(td.path()).unwrap();
+        assert!(matches!(
+            block_on(open(url)),
+            Err(Error::NotBranchError(..))
+        ));
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! This module re-exports commonly used items from the crate,
 pub use crate::branch::Branch;
+pub use crate::config::TypedConfig;
 pub use crate::controldir::ControlDir;
 pub use crate::error::Error as BrzError;
 pub use crate::repository::Repository;
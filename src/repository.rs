@@ -207,6 +207,20 @@ pub trait Repository {
         revision_id: &RevisionId,
     ) -> Result<bool, crate::error::Error>;
 
+    /// Sign a revision and store the signature.
+    ///
+    /// The repository must be write-locked and in a write group.
+    ///
+    /// # Arguments
+    ///
+    /// * `revision_id` - The revision to sign
+    /// * `gpg_strategy` - The GPG strategy to sign with
+    fn sign_revision(
+        &self,
+        revision_id: &RevisionId,
+        gpg_strategy: &crate::gpg::GPGStrategy,
+    ) -> Result<(), crate::error::Error>;
+
     /// Pack the repository to optimize storage.
     ///
     /// # Arguments
@@ -367,6 +381,35 @@ impl Revision {
     pub fn get_properties(&self) -> &std::collections::HashMap<String, String> {
         &self.properties
     }
+
+    /// Get the first line of the commit message.
+    pub fn get_summary(&self) -> String {
+        self.message
+            .trim_start_matches('\n')
+            .lines()
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    }
+
+    /// Get the authors of this revision.
+    ///
+    /// This uses the `authors` or `author` revision property if set, and
+    /// falls back to the committer otherwise.
+    pub fn get_apparent_authors(&self) -> Vec<String> {
+        if let Some(authors) = self.properties.get("authors") {
+            authors
+                .split('\n')
+                .filter(|a| !a.is_empty())
+                .map(|a| a.to_string())
+                .collect()
+        } else if let Some(author) = self.properties.get("author") {
+            vec![author.clone()]
+        } else {
+            vec![self.committer.clone()]
+        }
+    }
 }
 
 impl<'py> IntoPyObject<'py> for Revision {
@@ -775,6 +818,21 @@ impl<T: PyRepository> Repository for T {
         })
     }
 
+    fn sign_revision(
+        &self,
+        revision_id: &RevisionId,
+        gpg_strategy: &crate::gpg::GPGStrategy,
+    ) -> Result<(), crate::error::Error> {
+        Python::attach(|py| {
+            self.to_object(py).call_method1(
+                py,
+                "sign_revision",
+                (revision_id.clone(), gpg_strategy.to_object()),
+            )?;
+            Ok(())
+        })
+    }
+
     fn pack(
         &self,
         hint: Option<&[RevisionId]>,
@@ -0,0 +1,334 @@
+//! Revision specifiers, as accepted by the `-r` option of `brz`.
+//!
+//! A revision specifier is a string such as `revno:3`, `tag:v1.0` or
+//! `last:2` that identifies a revision relative to a branch.
+use crate::branch::{Branch, GenericBranch, MemoryBranch, PyBranch};
+use crate::error::Error;
+use crate::tree::RevisionTree;
+use crate::RevisionId;
+use pyo3::prelude::*;
+
+/// A parsed revision specifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevisionSpec {
+    /// A revision number, e.g. `revno:3`, `revno:1.2.1` or `revno:-1`,
+    /// optionally followed by `:<branch>`.
+    Revno(String),
+    /// A tag, e.g. `tag:v1.0`.
+    Tag(String),
+    /// The first revision committed on or after a date, e.g. `date:yesterday`
+    /// or `date:2024-01-01`.
+    Date(String),
+    /// The common ancestor with another branch, e.g. `ancestor:../trunk`.
+    Ancestor(String),
+    /// The left-hand parent of another revision, e.g. `before:tag:v1.0`.
+    Before(Box<RevisionSpec>),
+    /// The n-th revision from the end of the mainline, e.g. `last:2`.
+    Last(u32),
+    /// The tip of another branch, e.g. `branch:../feature`.
+    Branch(String),
+    /// An exact revision id, e.g. `revid:joe@example.com-20240101-abcdef`.
+    RevisionId(RevisionId),
+    /// A git commit, by full or abbreviated SHA, e.g. `git:a1b2c3d`.
+    Git(String),
+    /// A specifier without a prefix, such as `3` or `v1.0`; Breezy guesses
+    /// whether it is a revision number, revision id, tag or date.
+    Dwim(String),
+}
+
+impl std::fmt::Display for RevisionSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RevisionSpec::Revno(revno) => write!(f, "revno:{}", revno),
+            RevisionSpec::Tag(tag) => write!(f, "tag:{}", tag),
+            RevisionSpec::Date(date) => write!(f, "date:{}", date),
+            RevisionSpec::Ancestor(location) => write!(f, "ancestor:{}", location),
+            RevisionSpec::Before(spec) => write!(f, "before:{}", spec),
+            RevisionSpec::Last(n) => write!(f, "last:{}", n),
+            RevisionSpec::Branch(location) => write!(f, "branch:{}", location),
+            RevisionSpec::RevisionId(revid) => write!(f, "revid:{}", revid),
+            RevisionSpec::Git(sha) => write!(f, "git:{}", sha),
+            RevisionSpec::Dwim(spec) => write!(f, "{}", spec),
+        }
+    }
+}
+
+impl std::str::FromStr for RevisionSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RevisionSpec::parse(s)
+    }
+}
+
+fn invalid(spec: &str, extra: &str) -> Error {
+    Error::InvalidRevisionSpec(spec.to_string(), Some(extra.to_string()))
+}
+
+fn branch_object(py: Python, branch: &dyn Branch) -> Result<Py<PyAny>, Error> {
+    if let Some(gb) = branch.as_any().downcast_ref::<GenericBranch>() {
+        Ok(gb.to_object(py))
+    } else if let Some(mb) = branch.as_any().downcast_ref::<MemoryBranch>() {
+        Ok(mb.to_object(py))
+    } else {
+        Err(Error::Other(pyo3::exceptions::PyTypeError::new_err(
+            "Branch must be a PyBranch",
+        )))
+    }
+}
+
+impl RevisionSpec {
+    /// Parse a revision specifier.
+    ///
+    /// Strings without a known prefix are parsed as [`RevisionSpec::Dwim`].
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The revision specifier, e.g. `revno:3` or `tag:v1.0`
+    ///
+    /// # Returns
+    ///
+    /// The parsed specifier, or [`Error::InvalidRevisionSpec`] if it is malformed
+    pub fn parse(spec: &str) -> Result<Self, Error> {
+        if spec.is_empty() {
+            return Err(invalid(spec, "empty revision specifier"));
+        }
+        let (prefix, value) = match spec.split_once(':') {
+            Some((prefix, value)) => (prefix, value),
+            None => return Ok(RevisionSpec::Dwim(spec.to_string())),
+        };
+        match prefix {
+            "revno" => {
+                let number = value.split_once(':').map_or(value, |(n, _)| n);
+                let number = number.strip_prefix('-').unwrap_or(number);
+                if !number.is_empty()
+                    && !number
+                        .split('.')
+                        .all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+                {
+                    return Err(invalid(spec, "invalid revision number"));
+                }
+                Ok(RevisionSpec::Revno(value.to_string()))
+            }
+            "tag" if value.is_empty() => Err(invalid(spec, "tag name is required")),
+            "tag" => Ok(RevisionSpec::Tag(value.to_string())),
+            "date" => Ok(RevisionSpec::Date(value.to_string())),
+            "ancestor" if value.is_empty() => Err(invalid(spec, "location is required")),
+            "ancestor" => Ok(RevisionSpec::Ancestor(value.to_string())),
+            "before" => Ok(RevisionSpec::Before(Box::new(RevisionSpec::parse(value)?))),
+            "last" if value.is_empty() => Ok(RevisionSpec::Last(1)),
+            "last" => value
+                .parse()
+                .map(RevisionSpec::Last)
+                .map_err(|_| invalid(spec, "expected a non-negative number")),
+            "branch" if value.is_empty() => Err(invalid(spec, "location is required")),
+            "branch" => Ok(RevisionSpec::Branch(value.to_string())),
+            "revid" if value.is_empty() => Err(invalid(spec, "revision id is required")),
+            "revid" => Ok(RevisionSpec::RevisionId(RevisionId::from(
+                value.as_bytes().to_vec(),
+            ))),
+            "git" => {
+                if value.len() < 4
+                    || value.len() > 40
+                    || !value.chars().all(|c| c.is_ascii_hexdigit())
+                {
+                    return Err(invalid(spec, "expected a (partial) git SHA"));
+                }
+                Ok(RevisionSpec::Git(value.to_lowercase()))
+            }
+            _ => Ok(RevisionSpec::Dwim(spec.to_string())),
+        }
+    }
+
+    fn to_object(&self, py: Python) -> PyResult<Py<PyAny>> {
+        let m = py.import("breezy.revisionspec")?;
+        Ok(m.getattr("RevisionSpec")?
+            .call_method1("from_string", (self.to_string(),))?
+            .unbind())
+    }
+
+    /// Look up the revision in the history of a branch.
+    ///
+    /// # Arguments
+    ///
+    /// * `branch` - The branch to resolve the specifier against
+    ///
+    /// # Returns
+    ///
+    /// The revision number, if the revision is on the mainline of the
+    /// branch, and the revision id
+    pub fn in_history(&self, branch: &dyn Branch) -> Result<(Option<u32>, RevisionId), Error> {
+        Python::attach(|py| {
+            let branch = branch_object(py, branch)?;
+            let info = self
+                .to_object(py)?
+                .call_method1(py, "in_history", (branch,))?;
+            let revno = info.getattr(py, "revno")?.extract(py).unwrap_or(None);
+            let revid = info.getattr(py, "rev_id")?;
+            if revid.is_none(py) {
+                return Err(Error::InvalidRevisionSpec(self.to_string(), None));
+            }
+            Ok((revno, revid.extract(py)?))
+        })
+    }
+
+    /// Look up the revision id, which does not need to be in the history of the branch.
+    pub fn as_revision_id(&self, branch: &dyn Branch) -> Result<RevisionId, Error> {
+        Python::attach(|py| {
+            let branch = branch_object(py, branch)?;
+            Ok(self
+                .to_object(py)?
+                .call_method1(py, "as_revision_id", (branch,))?
+                .extract(py)?)
+        })
+    }
+
+    /// Get the tree for the revision.
+    pub fn as_tree(&self, branch: &dyn Branch) -> Result<RevisionTree, Error> {
+        Python::attach(|py| {
+            let branch = branch_object(py, branch)?;
+            Ok(RevisionTree(self.to_object(py)?.call_method1(
+                py,
+                "as_tree",
+                (branch,),
+            )?))
+        })
+    }
+}
+
+/// A parsed `-r` argument: either a single revision or a range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevisionRange {
+    /// A single revision, e.g. `-r 3`.
+    Single(RevisionSpec),
+    /// A range, e.g. `-r 3..5`; either end may be left open, as in `-r 3..`.
+    Range(Option<RevisionSpec>, Option<RevisionSpec>),
+}
+
+/// Split a revision argument on `..`, except where it is followed by a path
+/// separator (as in `branch:../other`).
+fn split_range(s: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut i = 0;
+    let bytes = s.as_bytes();
+    while i + 1 < bytes.len() {
+        if bytes[i] == b'.'
+            && bytes[i + 1] == b'.'
+            && !matches!(bytes.get(i + 2), Some(b'/') | Some(b'\\'))
+        {
+            parts.push(&s[start..i]);
+            i += 2;
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Parse a revision argument that may be a range.
+///
+/// # Arguments
+///
+/// * `s` - The argument, e.g. `3`, `tag:v1.0..` or `revno:2..last:1`
+///
+/// # Returns
+///
+/// The parsed revision or range
+pub fn parse_revision_range(s: &str) -> Result<RevisionRange, Error> {
+    let parse_end = |part: &str| {
+        if part.is_empty() {
+            Ok(None)
+        } else {
+            RevisionSpec::parse(part).map(Some)
+        }
+    };
+    match split_range(s).as_slice() {
+        [single] => Ok(RevisionRange::Single(RevisionSpec::parse(single)?)),
+        [start, end] => Ok(RevisionRange::Range(parse_end(start)?, parse_end(end)?)),
+        _ => Err(invalid(s, "a range can only have two ends")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+    use crate::workingtree::WorkingTree;
+    use serial_test::serial;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            RevisionSpec::parse("revno:3").unwrap(),
+            RevisionSpec::Revno("3".to_string())
+        );
+        assert_eq!(
+            RevisionSpec::parse("before:tag:v1.0").unwrap(),
+            RevisionSpec::Before(Box::new(RevisionSpec::Tag("v1.0".to_string())))
+        );
+        assert_eq!(RevisionSpec::parse("last:").unwrap(), RevisionSpec::Last(1));
+        assert_eq!(
+            RevisionSpec::parse("1.2.3").unwrap(),
+            RevisionSpec::Dwim("1.2.3".to_string())
+        );
+        assert!(matches!(
+            RevisionSpec::parse("git:xyz"),
+            Err(Error::InvalidRevisionSpec(..))
+        ));
+        assert!(RevisionSpec::parse("last:-1").is_err());
+        assert_eq!(
+            RevisionSpec::parse("revid:a@b-1").unwrap().to_string(),
+            "revid:a@b-1"
+        );
+    }
+
+    #[test]
+    fn test_parse_revision_range() {
+        assert_eq!(
+            parse_revision_range("1.2.3").unwrap(),
+            RevisionRange::Single(RevisionSpec::Dwim("1.2.3".to_string()))
+        );
+        assert_eq!(
+            parse_revision_range("revno:2..").unwrap(),
+            RevisionRange::Range(Some(RevisionSpec::Revno("2".to_string())), None)
+        );
+        assert_eq!(
+            parse_revision_range("branch:../trunk..last:1").unwrap(),
+            RevisionRange::Range(
+                Some(RevisionSpec::Branch("../trunk".to_string())),
+                Some(RevisionSpec::Last(1))
+            )
+        );
+        assert!(parse_revision_range("1..2..3").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_in_history() {
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(Path::new("."), &ControlDirFormat::default()).unwrap();
+        let revid = wt
+            .build_commit()
+            .message("Initial commit")
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap();
+        let branch = wt.branch();
+        branch.tags().unwrap().set_tag("v1", &revid).unwrap();
+
+        let spec = RevisionSpec::parse("tag:v1").unwrap();
+        assert_eq!(spec.in_history(&branch).unwrap(), (Some(1), revid.clone()));
+        let spec = RevisionSpec::parse("last:1").unwrap();
+        assert_eq!(spec.as_revision_id(&branch).unwrap(), revid);
+        assert!(matches!(
+            RevisionSpec::parse("tag:v2").unwrap().in_history(&branch),
+            Err(Error::NoSuchTag(_))
+        ));
+        std::mem::drop(env);
+    }
+}
@@ -0,0 +1,554 @@
+//! Shelving of uncommitted changes.
+//!
+//! Shelves store uncommitted changes from a working tree so that they can be
+//! restored later, similar to `brz shelve` and `brz unshelve`.
+use crate::error::Error;
+use crate::transform::{PreviewTree, TreeTransform};
+use crate::tree::{PyTree, TreeChange};
+use crate::workingtree::{PyWorkingTree, WorkingTree};
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use std::path::{Path, PathBuf};
+
+/// Identifier of a shelf.
+pub type ShelfId = u32;
+
+/// Summary of a stored shelf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShelfInfo {
+    /// The id of the shelf.
+    pub id: ShelfId,
+    /// The message recorded when the changes were shelved, if any.
+    pub message: Option<String>,
+}
+
+/// How to unshelve a set of changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnshelveMode {
+    /// Apply the changes to the working tree and delete the shelf.
+    #[default]
+    Apply,
+    /// Only show what would be unshelved; neither the tree nor the shelf is changed.
+    DryRun,
+    /// Apply the changes to the working tree but keep the shelf.
+    Keep,
+    /// Delete the shelf without applying its changes.
+    DeleteOnly,
+}
+
+impl UnshelveMode {
+    fn flags(self) -> (bool, bool, bool) {
+        // (apply_changes, delete_shelf, read_shelf)
+        match self {
+            UnshelveMode::Apply => (true, true, true),
+            UnshelveMode::DryRun => (false, false, true),
+            UnshelveMode::Keep => (true, false, true),
+            UnshelveMode::DeleteOnly => (false, true, false),
+        }
+    }
+}
+
+/// Manages the shelves of a working tree.
+pub struct ShelfManager(Py<PyAny>);
+
+impl From<Py<PyAny>> for ShelfManager {
+    fn from(obj: Py<PyAny>) -> Self {
+        ShelfManager(obj)
+    }
+}
+
+impl<'py> IntoPyObject<'py> for ShelfManager {
+    type Target = PyAny;
+    type Output = Bound<'py, Self::Target>;
+    type Error = std::convert::Infallible;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        Ok(self.0.into_bound(py))
+    }
+}
+
+impl ShelfManager {
+    /// Get the ids of all active shelves, in ascending order.
+    pub fn active_shelves(&self) -> Result<Vec<ShelfId>, Error> {
+        Python::attach(|py| {
+            Ok(self
+                .0
+                .call_method0(py, intern!(py, "active_shelves"))?
+                .extract(py)?)
+        })
+    }
+
+    /// Get the id of the most recently created shelf, if any.
+    pub fn last_shelf(&self) -> Result<Option<ShelfId>, Error> {
+        Python::attach(|py| Ok(self.0.call_method0(py, "last_shelf")?.extract(py)?))
+    }
+
+    /// Get the message stored with a shelf.
+    ///
+    /// # Arguments
+    ///
+    /// * `shelf_id` - The id of the shelf
+    ///
+    /// # Returns
+    ///
+    /// The message, or `None` if the changes were shelved without one
+    pub fn get_message(&self, shelf_id: ShelfId) -> Result<Option<String>, Error> {
+        Python::attach(|py| {
+            let metadata = self.0.call_method1(py, "get_metadata", (shelf_id,))?;
+            let message = metadata
+                .bind(py)
+                .call_method1("get", (PyBytes::new(py, b"message"),))?;
+            if message.is_none() {
+                return Ok(None);
+            }
+            if let Ok(message) = message.extract::<String>() {
+                Ok(Some(message))
+            } else {
+                let message: Vec<u8> = message.extract()?;
+                Ok(Some(String::from_utf8_lossy(&message).into_owned()))
+            }
+        })
+    }
+
+    /// List all active shelves along with their messages.
+    pub fn list_shelves(&self) -> Result<Vec<ShelfInfo>, Error> {
+        self.active_shelves()?
+            .into_iter()
+            .map(|id| {
+                Ok(ShelfInfo {
+                    id,
+                    message: self.get_message(id)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Store the changes selected in a shelf creator as a new shelf.
+    ///
+    /// The selected changes are removed from the working tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `creator` - The shelf creator with the changes to shelve
+    /// * `message` - Optional message to store with the shelf
+    ///
+    /// # Returns
+    ///
+    /// The id of the new shelf
+    pub fn shelve_changes(
+        &self,
+        creator: &ShelfCreator,
+        message: Option<&str>,
+    ) -> Result<ShelfId, Error> {
+        Python::attach(|py| {
+            Ok(self
+                .0
+                .call_method1(py, "shelve_changes", (creator.0.clone_ref(py), message))?
+                .extract(py)?)
+        })
+    }
+
+    /// Shelve all changes to the specified paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - The paths to shelve changes for, or `None` for the whole tree
+    /// * `message` - Optional message to store with the shelf
+    ///
+    /// # Returns
+    ///
+    /// The id of the new shelf, or `None` if there were no changes to shelve
+    pub fn shelve_paths(
+        &self,
+        paths: Option<&[&Path]>,
+        message: Option<&str>,
+    ) -> Result<Option<ShelfId>, Error> {
+        let tree = Python::attach(|py| -> PyResult<_> {
+            Ok(crate::workingtree::GenericWorkingTree::from(
+                self.0.getattr(py, "tree")?,
+            ))
+        })?;
+        let basis = tree.basis_tree()?;
+        let creator = ShelfCreator::new(&tree, &basis, paths)?;
+        if creator.iter_shelvable()?.is_empty() {
+            return Ok(None);
+        }
+        creator.shelve_all()?;
+        self.shelve_changes(&creator, message).map(Some)
+    }
+
+    /// Open a shelf to inspect its changes without applying them.
+    pub fn get_shelf(&self, shelf_id: ShelfId) -> Result<Shelf, Error> {
+        Python::attach(|py| {
+            let message = self.get_message(shelf_id)?;
+            let unshelver = self.0.call_method1(py, "get_unshelver", (shelf_id,))?;
+            let preview = (|| -> PyResult<Py<PyAny>> {
+                let merger = unshelver.call_method0(py, "make_merger")?;
+                let tree_merger = merger.call_method0(py, "make_merger")?;
+                tree_merger.call_method0(py, "make_preview_transform")
+            })();
+            let preview = match preview {
+                Ok(preview) => preview,
+                Err(e) => {
+                    unshelver.call_method0(py, "finalize")?;
+                    return Err(e.into());
+                }
+            };
+            Ok(Shelf {
+                id: shelf_id,
+                message,
+                unshelver,
+                preview,
+            })
+        })
+    }
+
+    /// Unshelve the changes stored in a shelf.
+    ///
+    /// # Arguments
+    ///
+    /// * `shelf_id` - The id of the shelf to unshelve
+    /// * `mode` - Whether to apply the changes and/or delete the shelf
+    pub fn unshelve(&self, shelf_id: ShelfId, mode: UnshelveMode) -> Result<(), Error> {
+        let (apply_changes, delete_shelf, read_shelf) = mode.flags();
+        Python::attach(|py| {
+            let m = py.import("breezy.shelf_ui")?;
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("apply_changes", apply_changes)?;
+            kwargs.set_item("delete_shelf", delete_shelf)?;
+            kwargs.set_item("read_shelf", read_shelf)?;
+            let unshelver = m.getattr("Unshelver")?.call(
+                (self.0.getattr(py, "tree")?, self.0.clone_ref(py), shelf_id),
+                Some(&kwargs),
+            )?;
+            unshelver.call_method0("run")?;
+            Ok(())
+        })
+    }
+
+    /// Delete a shelf without applying its changes.
+    pub fn delete_shelf(&self, shelf_id: ShelfId) -> Result<(), Error> {
+        Python::attach(|py| {
+            self.0.call_method1(py, "delete_shelf", (shelf_id,))?;
+            Ok(())
+        })
+    }
+}
+
+/// The kind of change that can be shelved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShelvableAction {
+    /// A file was added.
+    AddFile,
+    /// A file was deleted.
+    DeleteFile,
+    /// A file was renamed.
+    Rename,
+    /// A file changed kind (e.g. from file to symlink).
+    ChangeKind,
+    /// The target of a symlink was modified.
+    ModifyTarget,
+    /// The text of a file was modified.
+    ModifyText,
+}
+
+impl std::str::FromStr for ShelvableAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "add file" => Ok(ShelvableAction::AddFile),
+            "delete file" => Ok(ShelvableAction::DeleteFile),
+            "rename" => Ok(ShelvableAction::Rename),
+            "change kind" => Ok(ShelvableAction::ChangeKind),
+            "modify target" => Ok(ShelvableAction::ModifyTarget),
+            "modify text" => Ok(ShelvableAction::ModifyText),
+            _ => Err(format!("Unknown shelvable change: {}", s)),
+        }
+    }
+}
+
+/// A change in the working tree that can be shelved.
+pub struct ShelvableChange {
+    /// The kind of change.
+    pub action: ShelvableAction,
+    /// The path of the affected file, in the working tree if it still exists
+    /// there and in the target tree otherwise.
+    pub path: PathBuf,
+    raw: Py<PyAny>,
+}
+
+impl Clone for ShelvableChange {
+    fn clone(&self) -> Self {
+        Python::attach(|py| ShelvableChange {
+            action: self.action,
+            path: self.path.clone(),
+            raw: self.raw.clone_ref(py),
+        })
+    }
+}
+
+impl std::fmt::Debug for ShelvableChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ShelvableChange")
+            .field("action", &self.action)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Selects changes from a working tree to be shelved.
+///
+/// The creator holds a write lock on the working tree until it is dropped.
+pub struct ShelfCreator(Py<PyAny>);
+
+impl ShelfCreator {
+    /// Create a new shelf creator.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_tree` - The working tree to shelve changes from
+    /// * `target_tree` - The tree to revert shelved changes to, usually the basis tree
+    /// * `file_list` - Optional list of paths to restrict the changes to
+    pub fn new(
+        work_tree: &dyn PyWorkingTree,
+        target_tree: &dyn PyTree,
+        file_list: Option<&[&Path]>,
+    ) -> Result<Self, Error> {
+        Python::attach(|py| {
+            let m = py.import("breezy.shelf")?;
+            let file_list = file_list.map(|files| {
+                files
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect::<Vec<_>>()
+            });
+            let creator = m.getattr("ShelfCreator")?.call1((
+                work_tree.to_object(py),
+                target_tree.to_object(py),
+                file_list,
+            ))?;
+            Ok(ShelfCreator(creator.unbind()))
+        })
+    }
+
+    /// List the changes that can be shelved.
+    pub fn iter_shelvable(&self) -> Result<Vec<ShelvableChange>, Error> {
+        Python::attach(|py| {
+            let creator = self.0.bind(py);
+            let work_tree = creator.getattr("work_tree")?;
+            let target_tree = creator.getattr("target_tree")?;
+            let mut ret = vec![];
+            for change in creator.call_method0("iter_shelvable")?.try_iter()? {
+                let change = change?;
+                let action: String = change.get_item(0)?.extract()?;
+                let action = action
+                    .parse()
+                    .map_err(pyo3::exceptions::PyValueError::new_err)?;
+                let file_id = change.get_item(1)?;
+                let path: String = match work_tree.call_method1("id2path", (&file_id,)) {
+                    Ok(path) => path.extract()?,
+                    Err(_) => target_tree
+                        .call_method1("id2path", (&file_id,))?
+                        .extract()?,
+                };
+                ret.push(ShelvableChange {
+                    action,
+                    path: PathBuf::from(path),
+                    raw: change.unbind(),
+                });
+            }
+            Ok(ret)
+        })
+    }
+
+    /// Select a single change for shelving.
+    pub fn shelve_change(&self, change: &ShelvableChange) -> Result<(), Error> {
+        Python::attach(|py| {
+            self.0
+                .call_method1(py, "shelve_change", (change.raw.clone_ref(py),))?;
+            Ok(())
+        })
+    }
+
+    /// Select all changes for shelving.
+    pub fn shelve_all(&self) -> Result<(), Error> {
+        Python::attach(|py| {
+            self.0.call_method0(py, "shelve_all")?;
+            Ok(())
+        })
+    }
+
+    /// Shelve some of the hunks of a modified file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file in the working tree
+    /// * `new_lines` - The lines that should remain in the working tree; the
+    ///   difference with the current contents is shelved
+    pub fn shelve_lines(&self, path: &Path, new_lines: &[Vec<u8>]) -> Result<(), Error> {
+        Python::attach(|py| {
+            let file_id = self.0.getattr(py, "work_tree")?.call_method1(
+                py,
+                "path2id",
+                (path.to_string_lossy().to_string(),),
+            )?;
+            if file_id.is_none(py) {
+                return Err(Error::NoSuchFile(path.to_path_buf()));
+            }
+            let lines = new_lines
+                .iter()
+                .map(|l| PyBytes::new(py, l))
+                .collect::<Vec<_>>();
+            self.0.call_method1(py, "shelve_lines", (file_id, lines))?;
+            Ok(())
+        })
+    }
+
+    /// The transform that will be applied to the working tree.
+    pub fn work_transform(&self) -> Result<TreeTransform, Error> {
+        Python::attach(|py| Ok(TreeTransform::from(self.0.getattr(py, "work_transform")?)))
+    }
+
+    /// The transform that records the shelved changes.
+    pub fn shelf_transform(&self) -> Result<TreeTransform, Error> {
+        Python::attach(|py| Ok(TreeTransform::from(self.0.getattr(py, "shelf_transform")?)))
+    }
+}
+
+impl Drop for ShelfCreator {
+    fn drop(&mut self) {
+        Python::attach(|py| {
+            if let Err(e) = self.0.call_method0(py, "finalize") {
+                log::warn!("Failed to finalize shelf creator: {}", e);
+            }
+        });
+    }
+}
+
+/// A preview of the changes stored in a shelf.
+///
+/// Obtained through [`ShelfManager::get_shelf`]; the working tree is not
+/// modified.
+pub struct Shelf {
+    id: ShelfId,
+    message: Option<String>,
+    unshelver: Py<PyAny>,
+    preview: Py<PyAny>,
+}
+
+impl Shelf {
+    /// The id of this shelf.
+    pub fn id(&self) -> ShelfId {
+        self.id
+    }
+
+    /// The message stored with this shelf, if any.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The changes stored in this shelf, relative to the working tree.
+    pub fn changes(&self) -> Result<Vec<TreeChange>, Error> {
+        Python::attach(|py| {
+            let mut ret = vec![];
+            for change in self
+                .preview
+                .call_method0(py, "iter_changes")?
+                .bind(py)
+                .try_iter()?
+            {
+                ret.push(change?.extract()?);
+            }
+            Ok(ret)
+        })
+    }
+
+    /// The tree as it would look after unshelving.
+    pub fn preview_tree(&self) -> Result<PreviewTree, Error> {
+        Python::attach(|py| {
+            Ok(PreviewTree::from(
+                self.preview.call_method0(py, "get_preview_tree")?,
+            ))
+        })
+    }
+}
+
+impl Drop for Shelf {
+    fn drop(&mut self) {
+        Python::attach(|py| {
+            if let Err(e) = self.preview.call_method0(py, "finalize") {
+                log::warn!("Failed to finalize shelf preview: {}", e);
+            }
+            if let Err(e) = self.unshelver.call_method0(py, "finalize") {
+                log::warn!("Failed to finalize unshelver: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+    use crate::tree::MutableTree;
+    use crate::workingtree::WorkingTree;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_shelve_and_unshelve() {
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(Path::new("."), &ControlDirFormat::default()).unwrap();
+        std::fs::write("a", b"initial\n").unwrap();
+        wt.add(&[Path::new("a")]).unwrap();
+        wt.build_commit()
+            .message("Initial commit")
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap();
+        std::fs::write("a", b"changed\n").unwrap();
+
+        let manager = wt.get_shelf_manager().unwrap();
+        assert_eq!(manager.active_shelves().unwrap(), Vec::<ShelfId>::new());
+        let id = manager
+            .shelve_paths(None, Some("work in progress"))
+            .unwrap()
+            .unwrap();
+        assert!(!wt.has_changes().unwrap());
+        assert_eq!(
+            manager.list_shelves().unwrap(),
+            vec![ShelfInfo {
+                id,
+                message: Some("work in progress".to_string()),
+            }]
+        );
+
+        {
+            let shelf = manager.get_shelf(id).unwrap();
+            let changes = shelf.changes().unwrap();
+            assert_eq!(changes.len(), 1);
+            assert_eq!(changes[0].path.1.as_deref(), Some(Path::new("a")));
+        }
+
+        manager.unshelve(id, UnshelveMode::Keep).unwrap();
+        assert_eq!(std::fs::read("a").unwrap(), b"changed\n");
+        assert_eq!(manager.active_shelves().unwrap(), vec![id]);
+        manager.delete_shelf(id).unwrap();
+        assert_eq!(manager.last_shelf().unwrap(), None);
+        std::mem::drop(env);
+    }
+
+    #[test]
+    #[serial]
+    fn test_nothing_to_shelve() {
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(Path::new("."), &ControlDirFormat::default()).unwrap();
+        let manager = wt.get_shelf_manager().unwrap();
+        assert_eq!(manager.shelve_paths(None, None).unwrap(), None);
+        std::mem::drop(env);
+    }
+}
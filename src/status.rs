@@ -1,6 +1,14 @@
 //! Status reporting functions.
+use crate::branch::Branch;
+use crate::delta::TreeDelta;
+use crate::error::Error;
+use crate::repository::{Repository, Revision};
+use crate::tree::{Conflict, GenericTree, Kind, PyTree, Tree, TreeChange};
 use crate::workingtree::PyWorkingTree;
+use crate::RevisionId;
 use pyo3::prelude::*;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 /// Display the status of a working tree.
 ///
@@ -23,10 +31,270 @@ pub fn show_tree_status(wt: &dyn PyWorkingTree) -> crate::Result<()> {
     })
 }
 
+/// A merge that has not been committed yet.
+#[derive(Debug, Clone)]
+pub struct PendingMerge {
+    /// The revision id of the merged revision.
+    pub revision_id: RevisionId,
+    /// The merged revision, or `None` if it is a ghost.
+    pub revision: Option<Revision>,
+    /// Revisions brought in by this merge that are not in the ancestry of
+    /// the basis or of earlier pending merges, newest first.
+    pub merged: Vec<Revision>,
+}
+
+/// The status of a working tree.
+#[derive(Debug)]
+pub struct TreeStatus {
+    /// The versioned changes.
+    pub delta: TreeDelta,
+    /// Unknown (unversioned and not ignored) files.
+    pub unknowns: Vec<PathBuf>,
+    /// Conflicts in the working tree.
+    pub conflicts: Vec<Conflict>,
+    /// Merges that have not been committed yet.
+    pub pending_merges: Vec<PendingMerge>,
+}
+
+impl TreeStatus {
+    /// Check whether there is nothing to report.
+    pub fn is_clean(&self) -> bool {
+        !self.delta.has_changed()
+            && self.delta.missing.is_empty()
+            && self.unknowns.is_empty()
+            && self.conflicts.is_empty()
+            && self.pending_merges.is_empty()
+    }
+
+    /// Write the status in the format used by `brz status --short`.
+    ///
+    /// Each line starts with three status columns: the versioning state
+    /// (`+` added, `-` removed, `R` renamed, `?` unknown, `C` conflict,
+    /// `P` pending merge), the content state (`N` new, `D` deleted,
+    /// `K` kind changed, `M` modified) and `*` if the execute bit changed.
+    pub fn write_short(&self, mut w: impl Write) -> std::io::Result<()> {
+        for change in &self.delta.removed {
+            writeln!(w, "-D{} {}", exec_marker(change), old_path(change))?;
+        }
+        for change in &self.delta.added {
+            writeln!(w, "+N{} {}", exec_marker(change), new_path(change))?;
+        }
+        for change in &self.delta.renamed {
+            writeln!(
+                w,
+                "R{}{} {} => {}",
+                if change.changed_content { "M" } else { " " },
+                exec_marker(change),
+                old_path(change),
+                new_path(change)
+            )?;
+        }
+        for change in &self.delta.copied {
+            writeln!(
+                w,
+                "+N{} {} => {}",
+                exec_marker(change),
+                old_path(change),
+                new_path(change)
+            )?;
+        }
+        for change in &self.delta.kind_changed {
+            writeln!(w, " K{} {}", exec_marker(change), new_path(change))?;
+        }
+        for change in &self.delta.modified {
+            writeln!(
+                w,
+                " {}{} {}",
+                if change.changed_content { "M" } else { " " },
+                exec_marker(change),
+                new_path(change)
+            )?;
+        }
+        for change in &self.delta.missing {
+            writeln!(w, " D  {}", old_path(change))?;
+        }
+        for conflict in &self.conflicts {
+            writeln!(w, "C   {}", conflict.path.display())?;
+        }
+        for unknown in &self.unknowns {
+            writeln!(w, "?   {}", unknown.display())?;
+        }
+        for merge in &self.pending_merges {
+            match &merge.revision {
+                Some(rev) => writeln!(w, "P   {}", line_summary(rev))?,
+                None => writeln!(w, "P   (ghost) {}", merge.revision_id)?,
+            }
+            for rev in &merge.merged {
+                writeln!(w, "P.   {}", line_summary(rev))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the status in the format used by `brz status --short`.
+    pub fn to_short_string(&self) -> String {
+        let mut out = Vec::new();
+        self.write_short(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+}
+
+fn exec_marker(change: &TreeChange) -> &'static str {
+    match change.executable {
+        (Some(old), Some(new)) if old != new => "*",
+        _ => " ",
+    }
+}
+
+fn display_path(path: Option<&PathBuf>, kind: Option<&Kind>) -> String {
+    format!(
+        "{}{}",
+        path.map(|p| p.display().to_string()).unwrap_or_default(),
+        kind.map(|k| k.marker()).unwrap_or("")
+    )
+}
+
+fn old_path(change: &TreeChange) -> String {
+    display_path(change.path.0.as_ref(), change.kind.0.as_ref())
+}
+
+fn new_path(change: &TreeChange) -> String {
+    display_path(change.path.1.as_ref(), change.kind.1.as_ref())
+}
+
+fn line_summary(rev: &Revision) -> String {
+    format!(
+        "{} {} {}",
+        rev.get_apparent_authors()
+            .first()
+            .map(|a| crate::config::parse_username(a).0)
+            .unwrap_or_default(),
+        rev.datetime().format("%Y-%m-%d"),
+        rev.get_summary()
+    )
+}
+
+fn pending_merges(wt: &dyn PyWorkingTree) -> Result<Vec<PendingMerge>, Error> {
+    let parents = wt.get_parent_ids()?;
+    if parents.len() < 2 {
+        return Ok(vec![]);
+    }
+    let repository = wt.branch().repository();
+    let graph = repository.get_graph();
+    let mut seen = vec![parents[0].clone()];
+    let mut ret = vec![];
+    for merge in &parents[1..] {
+        let revision = match repository.get_revision(merge) {
+            Ok(rev) => Some(rev),
+            Err(Error::NoSuchRevision(_)) => None,
+            Err(e) => return Err(e),
+        };
+        let mut merged = vec![];
+        if revision.is_some() {
+            let unique = graph.find_unique_ancestors(std::slice::from_ref(merge), &seen)?;
+            let parent_map = graph
+                .get_parent_map(&unique)?
+                .into_iter()
+                .map(|(revid, parents)| {
+                    let parents = parents.into_iter().filter(|p| unique.contains(p)).collect();
+                    (revid, parents)
+                })
+                .collect();
+            for entry in crate::tsort::merge_sort(&parent_map, merge)? {
+                if &entry.node == merge {
+                    continue;
+                }
+                match repository.get_revision(&entry.node) {
+                    Ok(rev) => merged.push(rev),
+                    Err(Error::NoSuchRevision(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        seen.push(merge.clone());
+        ret.push(PendingMerge {
+            revision_id: merge.clone(),
+            revision,
+            merged,
+        });
+    }
+    Ok(ret)
+}
+
+/// Gather the status of a working tree.
+///
+/// # Arguments
+///
+/// * `wt` - The working tree to report on
+/// * `specific_files` - Only report on these paths, or `None` for the whole tree
+/// * `revision_range` - The revisions to compare; `None` compares the basis
+///   tree with the working tree, `(old, None)` compares `old` with the working
+///   tree and `(old, Some(new))` compares two revisions
+/// * `show_pending` - Whether to include pending merges. They are only
+///   reported when comparing against the basis tree.
+///
+/// # Returns
+///
+/// The status of the tree. Unknown files and conflicts are only reported
+/// when the working tree is part of the comparison.
+pub fn tree_status(
+    wt: &dyn PyWorkingTree,
+    specific_files: Option<&[&Path]>,
+    revision_range: Option<(&RevisionId, Option<&RevisionId>)>,
+    show_pending: bool,
+) -> Result<TreeStatus, Error> {
+    let _lock = wt.lock_read()?;
+    let (old_tree, new_tree) = match revision_range {
+        None => (wt.basis_tree()?, None),
+        Some((old, None)) => (*wt.revision_tree(old)?, None),
+        Some((old, Some(new))) => (*wt.revision_tree(old)?, Some(*wt.revision_tree(new)?)),
+    };
+    let _old_lock = old_tree.lock_read()?;
+    let includes_wt = new_tree.is_none();
+    let new_tree = match new_tree {
+        Some(tree) => GenericTree::from(Python::attach(|py| tree.to_object(py))),
+        None => GenericTree::from(Python::attach(|py| wt.to_object(py))),
+    };
+    let _new_lock = new_tree.lock_read()?;
+
+    let delta = crate::intertree::get(&old_tree, &new_tree).compare_with(specific_files, false)?;
+
+    let (unknowns, conflicts) = if includes_wt {
+        let in_scope = |p: &Path| match specific_files {
+            Some(files) => crate::osutils::is_inside_any(files, p),
+            None => true,
+        };
+        let unknowns = wt.unknowns()?.into_iter().filter(|p| in_scope(p)).collect();
+        let conflicts = wt
+            .conflicts()?
+            .into_iter()
+            .filter(|c| in_scope(&c.path))
+            .collect();
+        (unknowns, conflicts)
+    } else {
+        (vec![], vec![])
+    };
+
+    let pending_merges = if show_pending && revision_range.is_none() {
+        pending_merges(wt)?
+    } else {
+        vec![]
+    };
+
+    Ok(TreeStatus {
+        delta,
+        unknowns,
+        conflicts,
+        pending_merges,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::controldir::create_standalone_workingtree;
+    use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+    use crate::tree::MutableTree;
+    use serial_test::serial;
 
     #[test]
     fn test_show_tree_status() {
@@ -37,4 +305,28 @@ mod tests {
         let result = show_tree_status(&wt);
         assert!(result.is_ok());
     }
+
+    #[test]
+    #[serial]
+    fn test_tree_status() {
+        let env = crate::testing::TestEnv::new();
+        let wt =
+            create_standalone_workingtree(Path::new("."), &ControlDirFormat::default()).unwrap();
+        let status = tree_status(&wt, None, None, true).unwrap();
+        assert!(status.is_clean());
+
+        std::fs::write("a", b"contents").unwrap();
+        std::fs::write("b", b"contents").unwrap();
+        wt.add(&[Path::new("a")]).unwrap();
+        let status = tree_status(&wt, None, None, true).unwrap();
+        assert!(!status.is_clean());
+        assert_eq!(status.delta.added.len(), 1);
+        assert_eq!(status.unknowns, vec![PathBuf::from("b")]);
+        assert_eq!(status.to_short_string(), "+N  a\n?   b\n");
+
+        let status = tree_status(&wt, Some(&[Path::new("b")]), None, true).unwrap();
+        assert!(status.delta.added.is_empty());
+        assert_eq!(status.unknowns, vec![PathBuf::from("b")]);
+        std::mem::drop(env);
+    }
 }
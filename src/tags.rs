@@ -17,6 +17,69 @@ impl From<Py<PyAny>> for Tags {
     }
 }
 
+/// Selects which tags to copy between branches.
+pub struct TagSelector(Box<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl TagSelector {
+    /// Create a selector from a predicate on tag names.
+    pub fn new(f: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        TagSelector(Box::new(f))
+    }
+
+    /// Select all tags.
+    pub fn all() -> Self {
+        Self::new(|_| true)
+    }
+
+    /// Select only the given tags.
+    pub fn names<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names: HashSet<String> = names.into_iter().map(Into::into).collect();
+        Self::new(move |tag| names.contains(tag))
+    }
+
+    /// Select tags whose name starts with `prefix`.
+    pub fn prefix(prefix: &str) -> Self {
+        let prefix = prefix.to_string();
+        Self::new(move |tag| tag.starts_with(&prefix))
+    }
+
+    /// Check whether a tag is selected.
+    pub fn matches(&self, tag: &str) -> bool {
+        (self.0)(tag)
+    }
+}
+
+impl From<TagSelector> for Box<dyn Fn(String) -> bool> {
+    fn from(selector: TagSelector) -> Self {
+        Box::new(move |tag| selector.matches(&tag))
+    }
+}
+
+/// A tag that differs between two branches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagConflict {
+    /// The name of the tag.
+    pub name: String,
+    /// The revision the tag points to in the source branch.
+    pub source: RevisionId,
+    /// The revision the tag points to in the target branch.
+    pub target: RevisionId,
+}
+
+/// The result of merging tags into another branch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagMergeResult {
+    /// The tags that were changed in the target, with their new revision,
+    /// or `None` for tags that were removed.
+    pub updates: HashMap<String, Option<RevisionId>>,
+    /// The tags that were not changed because they conflict.
+    pub conflicts: Vec<TagConflict>,
+}
+
 impl Tags {
     /// Get a mapping from revision IDs to sets of tags.
     ///
@@ -111,4 +174,269 @@ impl Tags {
         Python::attach(|py| self.0.call_method1(py, "delete_tag", (tag,)))?;
         Ok(())
     }
+
+    fn lock_write(&self) -> Result<crate::lock::Lock, Error> {
+        Python::attach(|py| {
+            let lock = self
+                .0
+                .getattr(py, "branch")?
+                .call_method0(py, "lock_write")?;
+            Ok(crate::lock::Lock::from(lock))
+        })
+    }
+
+    /// Rename a tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_name` - The current name of the tag
+    /// * `new_name` - The new name of the tag
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, [`Error::NoSuchTag`] if there is no tag named
+    /// `old_name` or [`Error::TagAlreadyExists`] if there is already a tag
+    /// named `new_name`
+    pub fn rename_tag(&self, old_name: &str, new_name: &str) -> Result<(), Error> {
+        let _lock = self.lock_write()?;
+        let revision_id = self.lookup_tag(old_name)?;
+        if self.has_tag(new_name) {
+            return Err(Error::TagAlreadyExists(new_name.to_string()));
+        }
+        self.set_tag(new_name, &revision_id)?;
+        self.delete_tag(old_name)
+    }
+
+    /// Set several tags while holding a single write lock.
+    ///
+    /// # Arguments
+    ///
+    /// * `tags` - The tags to set, with the revision each should point to
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an error if the operation fails
+    pub fn set_tags_bulk(&self, tags: &HashMap<String, RevisionId>) -> Result<(), Error> {
+        let _lock = self.lock_write()?;
+        for (tag, revision_id) in tags {
+            self.set_tag(tag, revision_id)?;
+        }
+        Ok(())
+    }
+
+    /// Copy the tags into another tag collection.
+    ///
+    /// Tags that point to different revisions in both collections are only
+    /// changed if `overwrite` is set; otherwise they are reported as
+    /// conflicts.
+    ///
+    /// # Arguments
+    ///
+    /// * `to_tags` - The tags to merge into
+    /// * `overwrite` - Whether to overwrite conflicting tags
+    /// * `ignore_master` - Whether to leave the tags of the master branch alone
+    ///   if the target branch is bound
+    ///
+    /// # Returns
+    ///
+    /// The changed and conflicting tags
+    pub fn merge_to(
+        &self,
+        to_tags: &Tags,
+        overwrite: bool,
+        ignore_master: bool,
+    ) -> Result<TagMergeResult, Error> {
+        Python::attach(|py| {
+            let kwargs = pyo3::types::PyDict::new(py);
+            kwargs.set_item("overwrite", overwrite)?;
+            kwargs.set_item("ignore_master", ignore_master)?;
+            let result =
+                self.0
+                    .call_method(py, "merge_to", (to_tags.0.clone_ref(py),), Some(&kwargs))?;
+            let (updates, conflicts): (HashMap<String, Option<RevisionId>>, Py<PyAny>) =
+                result.extract(py)?;
+            let conflicts = conflicts.bind(py);
+            // The conflicts may be a set, so sort them for a stable order.
+            let mut conflicts = conflicts
+                .try_iter()?
+                .map(|c| {
+                    let (name, source, target) = c?.extract()?;
+                    Ok(TagConflict {
+                        name,
+                        source,
+                        target,
+                    })
+                })
+                .collect::<PyResult<Vec<_>>>()?;
+            conflicts.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(TagMergeResult { updates, conflicts })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch::Branch;
+    use crate::controldir::{create_standalone_workingtree, ControlDirFormat};
+    use crate::repository::Repository;
+    use crate::workingtree::{GenericWorkingTree, WorkingTree};
+    use serial_test::serial;
+    use std::path::Path;
+
+    fn commit(wt: &GenericWorkingTree, message: &str) -> RevisionId {
+        wt.build_commit()
+            .message(message)
+            .allow_pointless(true)
+            .reporter(&crate::commit::NullCommitReporter::new())
+            .commit()
+            .unwrap()
+    }
+
+    fn setup() -> (
+        GenericWorkingTree,
+        GenericWorkingTree,
+        RevisionId,
+        RevisionId,
+    ) {
+        std::fs::create_dir("a").unwrap();
+        let a =
+            create_standalone_workingtree(Path::new("a"), &ControlDirFormat::default()).unwrap();
+        let r1 = commit(&a, "One");
+        let r2 = commit(&a, "Two");
+        let b_url =
+            url::Url::from_directory_path(std::env::current_dir().unwrap().join("b")).unwrap();
+        let b = a
+            .controldir()
+            .sprout(b_url, None, Some(true), None, None)
+            .unwrap()
+            .open_workingtree()
+            .unwrap();
+        (a, b, r1, r2)
+    }
+
+    #[test]
+    #[serial]
+    fn test_merge_to() {
+        let env = crate::testing::TestEnv::new();
+        let (a, b, r1, r2) = setup();
+        let source = a.branch().tags().unwrap();
+        let target = b.branch().tags().unwrap();
+        source
+            .set_tags_bulk(&HashMap::from([
+                ("v1".to_string(), r1.clone()),
+                ("common".to_string(), r1.clone()),
+            ]))
+            .unwrap();
+        target.set_tag("common", &r2).unwrap();
+
+        let result = source.merge_to(&target, false, false).unwrap();
+        assert_eq!(
+            result.updates,
+            HashMap::from([("v1".to_string(), Some(r1.clone()))])
+        );
+        assert_eq!(
+            result.conflicts,
+            vec![TagConflict {
+                name: "common".to_string(),
+                source: r1.clone(),
+                target: r2.clone(),
+            }]
+        );
+        assert_eq!(target.lookup_tag("common").unwrap(), r2);
+
+        let result = source.merge_to(&target, true, false).unwrap();
+        assert!(result.conflicts.is_empty());
+        assert_eq!(target.lookup_tag("common").unwrap(), r1);
+        std::mem::drop(env);
+    }
+
+    #[test]
+    #[serial]
+    fn test_rename_tag() {
+        let env = crate::testing::TestEnv::new();
+        let (a, _b, r1, r2) = setup();
+        let tags = a.branch().tags().unwrap();
+        tags.set_tag("old", &r1).unwrap();
+        tags.set_tag("other", &r2).unwrap();
+        tags.rename_tag("old", "new").unwrap();
+        assert!(!tags.has_tag("old"));
+        assert_eq!(tags.lookup_tag("new").unwrap(), r1);
+        assert!(matches!(
+            tags.rename_tag("new", "other"),
+            Err(Error::TagAlreadyExists(tag)) if tag == "other"
+        ));
+        assert!(matches!(
+            tags.rename_tag("missing", "x"),
+            Err(Error::NoSuchTag(..))
+        ));
+        std::mem::drop(env);
+    }
+
+    #[test]
+    #[serial]
+    fn test_pull_with_tags() {
+        let env = crate::testing::TestEnv::new();
+        let (a, b, r1, r2) = setup();
+        a.branch()
+            .tags()
+            .unwrap()
+            .set_tags_bulk(&HashMap::from([
+                ("release-1".to_string(), r1.clone()),
+                ("wip".to_string(), r2.clone()),
+            ]))
+            .unwrap();
+        b.branch()
+            .pull_with_tags(&a.branch(), None, TagSelector::prefix("release-"))
+            .unwrap();
+        assert_eq!(
+            b.branch().tags().unwrap().get_tag_dict().unwrap(),
+            HashMap::from([("release-1".to_string(), r1)])
+        );
+        std::mem::drop(env);
+    }
+
+    #[test]
+    #[serial]
+    fn test_fetch_with_tags() {
+        let env = crate::testing::TestEnv::new();
+        let (a, b, _r1, r2) = setup();
+        let r3 = commit(&a, "Three");
+        let r4 = commit(&a, "Four");
+        a.branch()
+            .tags()
+            .unwrap()
+            .set_tags_bulk(&HashMap::from([
+                ("release-2".to_string(), r3.clone()),
+                ("wip".to_string(), r4.clone()),
+            ]))
+            .unwrap();
+        a.branch().generate_revision_history(&r2).unwrap();
+        b.branch()
+            .fetch_with_tags(&a.branch(), None, TagSelector::prefix("release-"))
+            .unwrap();
+        let repository = b.branch().repository();
+        assert!(repository.has_revision(&r3).unwrap());
+        assert!(!repository.has_revision(&r4).unwrap());
+        assert_eq!(b.branch().last_revision(), r2);
+        assert!(b
+            .branch()
+            .tags()
+            .unwrap()
+            .get_tag_dict()
+            .unwrap()
+            .is_empty());
+        std::mem::drop(env);
+    }
+
+    #[test]
+    fn test_tag_selector() {
+        let selector = TagSelector::names(["a", "b"]);
+        assert!(selector.matches("a"));
+        assert!(!selector.matches("c"));
+        let f: Box<dyn Fn(String) -> bool> = TagSelector::prefix("v").into();
+        assert!(f("v1".to_string()));
+        assert!(!f("1".to_string()));
+        assert!(TagSelector::all().matches("anything"));
+    }
 }
@@ -87,6 +87,12 @@ impl From<Py<PyAny>> for PreviewTree {
     }
 }
 
+impl PyTree for PreviewTree {
+    fn to_object(&self, py: Python) -> Py<PyAny> {
+        self.0.clone_ref(py)
+    }
+}
+
 impl TreeTransform {
     fn to_object(&self) -> &Py<PyAny> {
         &self.0
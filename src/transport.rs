@@ -1,8 +1,42 @@
 //! Transport module
 use crate::error::Error;
 use pyo3::prelude::*;
-use pyo3::types::PyDict;
+use pyo3::types::{PyBytes, PyDict, PyTuple};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Metadata about a file on a transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stat {
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The file mode, including the file type bits.
+    pub mode: u32,
+}
+
+impl Stat {
+    /// Check whether this is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.mode & 0o170000 == 0o040000
+    }
+
+    /// Check whether this is a regular file.
+    pub fn is_file(&self) -> bool {
+        self.mode & 0o170000 == 0o100000
+    }
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for Stat {
+    type Error = PyErr;
+
+    fn extract(ob: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        Ok(Stat {
+            size: ob.getattr("st_size")?.extract()?,
+            mode: ob.getattr("st_mode")?.extract()?,
+        })
+    }
+}
 
 /// A transport represents a way to access content in a branch.
 pub struct Transport(Py<PyAny>);
@@ -86,6 +120,190 @@ impl Transport {
         })
     }
 
+    /// Read the contents of a file.
+    pub fn get_bytes(&self, path: &str) -> Result<Vec<u8>, Error> {
+        pyo3::Python::attach(|py| {
+            Ok(self
+                .0
+                .call_method1(py, "get_bytes", (path,))?
+                .extract::<Vec<u8>>(py)?)
+        })
+    }
+
+    /// Open a file for reading.
+    pub fn get(&self, path: &str) -> Result<impl std::io::Read, Error> {
+        pyo3::Python::attach(|py| {
+            let f = self.0.call_method1(py, "get", (path,))?;
+            Ok(pyo3_filelike::PyBinaryFile::from(f))
+        })
+    }
+
+    /// Read ranges of a file.
+    ///
+    /// # Arguments
+    /// * `path` - The file to read from
+    /// * `ranges` - The offsets and lengths of the ranges to read
+    ///
+    /// # Returns
+    /// The offset and contents of each range, in the order they were requested
+    pub fn readv(&self, path: &str, ranges: &[(u64, usize)]) -> Result<Vec<(u64, Vec<u8>)>, Error> {
+        pyo3::Python::attach(|py| {
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("adjust_for_latency", false)?;
+            self.0
+                .call_method(py, "readv", (path, ranges.to_vec()), Some(&kwargs))?
+                .bind(py)
+                .try_iter()?
+                .map(|r| Ok(r?.extract::<(u64, Vec<u8>)>()?))
+                .collect()
+        })
+    }
+
+    /// Write the contents of a file, replacing it if it exists.
+    ///
+    /// # Arguments
+    /// * `path` - The file to write
+    /// * `data` - The new contents of the file
+    /// * `mode` - The permissions of the file, or `None` for the default
+    pub fn put_bytes(&self, path: &str, data: &[u8], mode: Option<u32>) -> Result<(), Error> {
+        pyo3::Python::attach(|py| {
+            self.0
+                .call_method1(py, "put_bytes", (path, PyBytes::new(py, data), mode))?;
+            Ok(())
+        })
+    }
+
+    /// Write the contents of a file from a reader, replacing it if it exists.
+    ///
+    /// # Arguments
+    /// * `path` - The file to write
+    /// * `f` - The reader to take the new contents of the file from
+    /// * `mode` - The permissions of the file, or `None` for the default
+    ///
+    /// # Returns
+    /// The number of bytes written
+    pub fn put_file(
+        &self,
+        path: &str,
+        f: &mut dyn std::io::Read,
+        mode: Option<u32>,
+    ) -> Result<u64, Error> {
+        let mut data = Vec::new();
+        f.read_to_end(&mut data).map_err(PyErr::from)?;
+        pyo3::Python::attach(|py| {
+            let f = py
+                .import("io")?
+                .call_method1("BytesIO", (PyBytes::new(py, &data),))?;
+            self.0.call_method1(py, "put_file", (path, f, mode))?;
+            Ok(data.len() as u64)
+        })
+    }
+
+    /// Append to a file, creating it if it does not exist.
+    ///
+    /// # Arguments
+    /// * `path` - The file to append to
+    /// * `data` - The data to append
+    /// * `mode` - The permissions of the file if it is created, or `None` for the default
+    ///
+    /// # Returns
+    /// The size of the file before the data was appended
+    pub fn append_bytes(&self, path: &str, data: &[u8], mode: Option<u32>) -> Result<u64, Error> {
+        pyo3::Python::attach(|py| {
+            Ok(self
+                .0
+                .call_method1(py, "append_bytes", (path, PyBytes::new(py, data), mode))?
+                .extract::<u64>(py)?)
+        })
+    }
+
+    /// Create a directory.
+    ///
+    /// # Arguments
+    /// * `path` - The directory to create; its parent must exist
+    /// * `mode` - The permissions of the directory, or `None` for the default
+    pub fn mkdir(&self, path: &str, mode: Option<u32>) -> Result<(), Error> {
+        pyo3::Python::attach(|py| {
+            self.0.call_method1(py, "mkdir", (path, mode))?;
+            Ok(())
+        })
+    }
+
+    /// Remove an empty directory.
+    pub fn rmdir(&self, path: &str) -> Result<(), Error> {
+        pyo3::Python::attach(|py| {
+            self.0.call_method1(py, "rmdir", (path,))?;
+            Ok(())
+        })
+    }
+
+    /// Delete a file.
+    pub fn delete(&self, path: &str) -> Result<(), Error> {
+        pyo3::Python::attach(|py| {
+            self.0.call_method1(py, "delete", (path,))?;
+            Ok(())
+        })
+    }
+
+    /// Rename a file or directory.
+    ///
+    /// Fails if the target already exists.
+    pub fn rename(&self, from: &str, to: &str) -> Result<(), Error> {
+        pyo3::Python::attach(|py| {
+            self.0.call_method1(py, "rename", (from, to))?;
+            Ok(())
+        })
+    }
+
+    /// List the names of the entries in a directory.
+    pub fn list_dir(&self, path: &str) -> Result<Vec<String>, Error> {
+        pyo3::Python::attach(|py| {
+            Ok(self
+                .0
+                .call_method1(py, "list_dir", (path,))?
+                .extract::<Vec<String>>(py)?)
+        })
+    }
+
+    /// List the paths of all files below the base of this transport.
+    ///
+    /// The paths are relative to the base and URL-escaped.
+    pub fn iter_files_recursive(&self) -> Result<Vec<String>, Error> {
+        pyo3::Python::attach(|py| {
+            self.0
+                .call_method0(py, "iter_files_recursive")?
+                .bind(py)
+                .try_iter()?
+                .map(|r| Ok(r?.extract::<String>()?))
+                .collect()
+        })
+    }
+
+    /// Get metadata about a file.
+    pub fn stat(&self, path: &str) -> Result<Stat, Error> {
+        pyo3::Python::attach(|py| Ok(self.0.call_method1(py, "stat", (path,))?.extract(py)?))
+    }
+
+    /// Take a write lock on a file.
+    ///
+    /// The lock is released when the returned guard is dropped.
+    pub fn lock_write(&self, path: &str) -> Result<crate::lock::Lock, Error> {
+        pyo3::Python::attach(|py| {
+            let lock = self.0.call_method1(py, "lock_write", (path,))?;
+            Ok(crate::lock::Lock::from(lock))
+        })
+    }
+
+    /// Take a read lock on a file.
+    ///
+    /// The lock is released when the returned guard is dropped.
+    pub fn lock_read(&self, path: &str) -> Result<crate::lock::Lock, Error> {
+        pyo3::Python::attach(|py| {
+            let lock = self.0.call_method1(py, "lock_read", (path,))?;
+            Ok(crate::lock::Lock::from(lock))
+        })
+    }
+
     /// Create a new transport with a different path.
     pub fn clone(&self, path: &str) -> Result<Transport, Error> {
         pyo3::Python::attach(|py| {
@@ -138,6 +356,481 @@ pub fn get_transport(
     })
 }
 
+/// A storage backend implemented in Rust that can be exposed to Breezy as a transport.
+///
+/// Paths passed to the backend are the path component of the URL, use `/` as
+/// separator, are not URL-escaped and never start with a `/`. The root itself
+/// is the empty string. The scheme and host of the URL are not part of the
+/// path, so a backend that serves several hosts (such as buckets of an object
+/// store) should be registered once per host, e.g. for `"objstore://bucket/"`.
+///
+/// Errors should use the variants Breezy expects, such as [`Error::NoSuchFile`]
+/// for missing paths and [`Error::FileExists`] for paths that already exist.
+pub trait TransportBackend: Send + Sync {
+    /// Read the contents of a file.
+    fn get_bytes(&self, path: &str) -> Result<Vec<u8>, Error>;
+
+    /// Write the contents of a file, replacing it if it already exists.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file
+    /// * `data` - The new contents of the file
+    /// * `mode` - The permissions of the file, if the backend supports them
+    fn put_bytes(&self, path: &str, data: &[u8], mode: Option<u32>) -> Result<(), Error>;
+
+    /// Append to a file, creating it if it does not exist.
+    ///
+    /// # Returns
+    /// The size of the file before the data was appended
+    fn append_bytes(&self, path: &str, data: &[u8], mode: Option<u32>) -> Result<u64, Error> {
+        let mut contents = match self.get_bytes(path) {
+            Ok(contents) => contents,
+            Err(Error::NoSuchFile(_)) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let offset = contents.len() as u64;
+        contents.extend_from_slice(data);
+        self.put_bytes(path, &contents, mode)?;
+        Ok(offset)
+    }
+
+    /// Check whether a path exists.
+    fn has(&self, path: &str) -> Result<bool, Error> {
+        match self.stat(path) {
+            Ok(_) => Ok(true),
+            Err(Error::NoSuchFile(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Create a directory.
+    fn mkdir(&self, path: &str, mode: Option<u32>) -> Result<(), Error>;
+
+    /// Remove an empty directory.
+    fn rmdir(&self, path: &str) -> Result<(), Error>;
+
+    /// Remove a file.
+    fn delete(&self, path: &str) -> Result<(), Error>;
+
+    /// Rename a file or directory.
+    ///
+    /// The destination may be overwritten if it is a file or an empty
+    /// directory, but the rename must fail if it is a non-empty directory.
+    fn rename(&self, from: &str, to: &str) -> Result<(), Error>;
+
+    /// List the names of the entries in a directory.
+    fn list_dir(&self, path: &str) -> Result<Vec<String>, Error>;
+
+    /// Get metadata about a file or directory.
+    fn stat(&self, path: &str) -> Result<Stat, Error>;
+
+    /// Check whether the backend is read-only.
+    fn is_readonly(&self) -> bool {
+        false
+    }
+}
+
+/// Arguments of a call to a method of a Python class implemented in Rust.
+///
+/// Index 0 is the object itself.
+pub(crate) struct MethodArgs<'a, 'py> {
+    args: &'a Bound<'py, PyTuple>,
+    kwargs: Option<&'a Bound<'py, PyDict>>,
+}
+
+impl<'py> MethodArgs<'_, 'py> {
+    pub(crate) fn py(&self) -> Python<'py> {
+        self.args.py()
+    }
+
+    pub(crate) fn this(&self) -> PyResult<Bound<'py, PyAny>> {
+        self.args.get_item(0)
+    }
+
+    pub(crate) fn get(&self, index: usize, name: &str) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let value = if index < self.args.len() {
+            Some(self.args.get_item(index)?)
+        } else if let Some(kwargs) = self.kwargs {
+            kwargs.get_item(name)?
+        } else {
+            None
+        };
+        Ok(value.filter(|v| !v.is_none()))
+    }
+
+    pub(crate) fn required(&self, index: usize, name: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.get(index, name)?.ok_or_else(|| {
+            pyo3::exceptions::PyTypeError::new_err(format!("missing argument: {}", name))
+        })
+    }
+
+    fn path(&self, index: usize, name: &str) -> PyResult<String> {
+        let relpath: String = self.required(index, name)?.extract()?;
+        backend_path(&self.this()?, &relpath)
+    }
+
+    fn mode(&self, index: usize) -> PyResult<Option<u32>> {
+        self.get(index, "mode")?.map(|m| m.extract()).transpose()
+    }
+}
+
+/// Parse the base URL of a transport.
+fn transport_base(transport: &Bound<PyAny>) -> PyResult<url::Url> {
+    let base: String = transport.getattr("base")?.extract()?;
+    url::Url::parse(&base).map_err(|e| {
+        pyo3::exceptions::PyValueError::new_err(format!("invalid URL {}: {}", base, e))
+    })
+}
+
+/// Resolve a URL-escaped path relative to a transport to a backend path.
+///
+/// Only the path of the base URL is taken into account; `..` segments never
+/// go above its root.
+fn backend_path(transport: &Bound<PyAny>, relpath: &str) -> PyResult<String> {
+    let urlutils = transport.py().import("breezy.urlutils")?;
+    let base = transport_base(transport)?;
+    let cwd: String = urlutils
+        .call_method1("unescape", (base.path(),))?
+        .extract()?;
+    let relpath: String = urlutils.call_method1("unescape", (relpath,))?.extract()?;
+    let segments: Vec<&str> = if relpath.starts_with('/') {
+        relpath.split('/').collect()
+    } else {
+        cwd.split('/').chain(relpath.split('/')).collect()
+    };
+    let mut parts = Vec::new();
+    for segment in segments {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            segment => parts.push(segment),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+/// Build the URL for a backend path, keeping the scheme and authority of the
+/// transport's base URL.
+fn backend_url(transport: &Bound<PyAny>, path: &str) -> PyResult<String> {
+    let mut url = transport_base(transport)?;
+    let path: String = transport
+        .py()
+        .import("breezy.urlutils")?
+        .call_method1("escape", (path,))?
+        .extract()?;
+    url.set_path(&format!("/{}", path));
+    Ok(url.to_string())
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn list_files(
+    backend: &dyn TransportBackend,
+    dir: &str,
+    prefix: &str,
+    files: &mut Vec<String>,
+) -> Result<(), Error> {
+    for name in backend.list_dir(dir)? {
+        let path = join_path(dir, &name);
+        let relpath = join_path(prefix, &name);
+        if backend.stat(&path)?.is_dir() {
+            list_files(backend, &path, &relpath, files)?;
+        } else {
+            files.push(relpath);
+        }
+    }
+    Ok(())
+}
+
+fn method_function<'py, R>(
+    py: Python<'py>,
+    f: impl Fn(&MethodArgs) -> PyResult<R> + Send + Sync + 'static,
+) -> PyResult<Bound<'py, pyo3::types::PyCFunction>>
+where
+    R: for<'a> IntoPyObject<'a>,
+{
+    pyo3::types::PyCFunction::new_closure(py, None, None, move |args, kwargs| {
+        f(&MethodArgs { args, kwargs })
+    })
+}
+
+/// Add a method implemented by a Rust closure to a class namespace.
+pub(crate) fn add_method<R>(
+    namespace: &Bound<PyDict>,
+    name: &str,
+    f: impl Fn(&MethodArgs) -> PyResult<R> + Send + Sync + 'static,
+) -> PyResult<()>
+where
+    R: for<'py> IntoPyObject<'py>,
+{
+    let py = namespace.py();
+    let f = method_function(py, f)?;
+    // partialmethod binds the object as the first argument, which plain
+    // builtin functions don't do.
+    let method = py
+        .import("functools")?
+        .getattr("partialmethod")?
+        .call1((f,))?;
+    namespace.set_item(name, method)?;
+    Ok(())
+}
+
+/// Add a class method implemented by a Rust closure to a class namespace.
+///
+/// Index 0 of the arguments is the class.
+pub(crate) fn add_classmethod<R>(
+    namespace: &Bound<PyDict>,
+    name: &str,
+    f: impl Fn(&MethodArgs) -> PyResult<R> + Send + Sync + 'static,
+) -> PyResult<()>
+where
+    R: for<'py> IntoPyObject<'py>,
+{
+    let py = namespace.py();
+    let f = method_function(py, f)?;
+    let method = py.import("builtins")?.getattr("classmethod")?.call1((f,))?;
+    namespace.set_item(name, method)?;
+    Ok(())
+}
+
+fn transport_class<'py>(
+    py: Python<'py>,
+    backend: Arc<dyn TransportBackend>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let base_class = py.import("breezy.transport")?.getattr("Transport")?;
+    let namespace = PyDict::new(py);
+
+    let parent = base_class.clone().unbind();
+    add_method(&namespace, "__init__", move |args| {
+        let mut base: String = args.required(1, "base")?.extract()?;
+        if !base.ends_with('/') {
+            base.push('/');
+        }
+        parent.call_method1(args.args.py(), "__init__", (args.this()?, base))?;
+        Ok(())
+    })?;
+
+    add_method(&namespace, "clone", |args| {
+        let this = args.this()?;
+        let base = match args.get(1, "offset")? {
+            Some(offset) => {
+                let path = backend_path(&this, &offset.extract::<String>()?)?;
+                backend_url(&this, &join_path(&path, ""))?
+            }
+            None => this.getattr("base")?.extract()?,
+        };
+        Ok(this.get_type().call1((base,))?.unbind())
+    })?;
+
+    add_method(&namespace, "abspath", |args| {
+        backend_url(&args.this()?, &args.path(1, "relpath")?)
+    })?;
+
+    add_method(&namespace, "external_url", |args| {
+        Ok(args.this()?.getattr("base")?.unbind())
+    })?;
+
+    add_method(&namespace, "listable", |_args| Ok(true))?;
+
+    add_method(&namespace, "_can_roundtrip_unix_modebits", |_args| {
+        Ok(false)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "is_readonly", move |_args| Ok(b.is_readonly()))?;
+
+    let b = backend.clone();
+    add_method(&namespace, "has", move |args| {
+        Ok(b.has(&args.path(1, "relpath")?)?)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "get_bytes", move |args| {
+        let data = b.get_bytes(&args.path(1, "relpath")?)?;
+        Ok(PyBytes::new(args.args.py(), &data).unbind())
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "get", move |args| {
+        let py = args.args.py();
+        let data = b.get_bytes(&args.path(1, "relpath")?)?;
+        Ok(py
+            .import("io")?
+            .call_method1("BytesIO", (PyBytes::new(py, &data),))?
+            .unbind())
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "put_bytes", move |args| {
+        let data: Vec<u8> = args.required(2, "raw_bytes")?.extract()?;
+        b.put_bytes(&args.path(1, "relpath")?, &data, args.mode(3)?)?;
+        Ok(())
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "put_file", move |args| {
+        let data: Vec<u8> = args.required(2, "f")?.call_method0("read")?.extract()?;
+        b.put_bytes(&args.path(1, "relpath")?, &data, args.mode(3)?)?;
+        Ok(data.len())
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "append_bytes", move |args| {
+        let data: Vec<u8> = args.required(2, "data")?.extract()?;
+        Ok(b.append_bytes(&args.path(1, "relpath")?, &data, args.mode(3)?)?)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "append_file", move |args| {
+        let data: Vec<u8> = args.required(2, "f")?.call_method0("read")?.extract()?;
+        Ok(b.append_bytes(&args.path(1, "relpath")?, &data, args.mode(3)?)?)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "open_write_stream", move |args| {
+        let py = args.args.py();
+        let this = args.this()?;
+        let relpath = args.required(1, "relpath")?;
+        b.put_bytes(&args.path(1, "relpath")?, b"", args.mode(2)?)?;
+        let m = py.import("breezy.transport")?;
+        let stream = m
+            .getattr("AppendBasedFileStream")?
+            .call1((&this, &relpath))?;
+        m.getattr("_file_streams")?
+            .set_item(this.call_method1("abspath", (&relpath,))?, &stream)?;
+        Ok(stream.unbind())
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "mkdir", move |args| {
+        Ok(b.mkdir(&args.path(1, "relpath")?, args.mode(2)?)?)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "rmdir", move |args| {
+        Ok(b.rmdir(&args.path(1, "relpath")?)?)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "delete", move |args| {
+        Ok(b.delete(&args.path(1, "relpath")?)?)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "rename", move |args| {
+        Ok(b.rename(&args.path(1, "rel_from")?, &args.path(2, "rel_to")?)?)
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "list_dir", move |args| {
+        let urlutils = args.args.py().import("breezy.urlutils")?;
+        b.list_dir(&args.path(1, "relpath")?)?
+            .into_iter()
+            .map(|name| Ok(urlutils.call_method1("escape", (name,))?.unbind()))
+            .collect::<PyResult<Vec<_>>>()
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "iter_files_recursive", move |args| {
+        let urlutils = args.args.py().import("breezy.urlutils")?;
+        let mut files = Vec::new();
+        list_files(
+            b.as_ref(),
+            &backend_path(&args.this()?, ".")?,
+            "",
+            &mut files,
+        )?;
+        files
+            .into_iter()
+            .map(|path| Ok(urlutils.call_method1("escape", (path,))?.unbind()))
+            .collect::<PyResult<Vec<_>>>()
+    })?;
+
+    let b = backend.clone();
+    add_method(&namespace, "stat", move |args| {
+        let stat = b.stat(&args.path(1, "relpath")?)?;
+        Ok(args
+            .args
+            .py()
+            .import("os")?
+            .getattr("stat_result")?
+            .call1(((stat.mode, 0, 0, 0, 0, 0, stat.size, 0, 0, 0),))?
+            .unbind())
+    })?;
+
+    add_method(&namespace, "lock_read", |args| {
+        let py = args.args.py();
+        let unlock = pyo3::types::PyCFunction::new_closure(
+            py,
+            None,
+            None,
+            |_args: &Bound<PyTuple>, _kwargs: Option<&Bound<PyDict>>| -> PyResult<()> { Ok(()) },
+        )?;
+        Ok(py
+            .import("breezy.lock")?
+            .getattr("LogicalLockResult")?
+            .call1((unlock,))?
+            .unbind())
+    })?;
+
+    let held = Arc::new(Mutex::new(HashSet::new()));
+    add_method(&namespace, "lock_write", move |args| {
+        let py = args.args.py();
+        let path = args.path(1, "relpath")?;
+        if !held.lock().unwrap().insert(path.clone()) {
+            return Err(Error::LockContention(path, String::new()).into());
+        }
+        let held = held.clone();
+        let unlock = pyo3::types::PyCFunction::new_closure(
+            py,
+            None,
+            None,
+            move |_args: &Bound<PyTuple>, _kwargs: Option<&Bound<PyDict>>| -> PyResult<()> {
+                held.lock().unwrap().remove(&path);
+                Ok(())
+            },
+        )?;
+        Ok(py
+            .import("breezy.lock")?
+            .getattr("LogicalLockResult")?
+            .call1((unlock,))?
+            .unbind())
+    })?;
+
+    py.import("builtins")?
+        .getattr("type")?
+        .call1(("RustTransport", (base_class,), namespace))
+}
+
+/// Register a Rust transport backend for a URL prefix.
+///
+/// After registration, [`get_transport`] and everything built on top of it,
+/// such as [`crate::controldir::open`] and [`crate::branch::open`], can open
+/// URLs that start with `prefix`.
+///
+/// # Arguments
+/// * `prefix` - The URL prefix to register, e.g. `"objstore://"`
+/// * `backend` - The backend that stores the data
+pub fn register_transport(prefix: &str, backend: Box<dyn TransportBackend>) -> Result<(), Error> {
+    pyo3::Python::attach(|py| {
+        let m = py.import("breezy.transport")?;
+        let class = transport_class(py, Arc::from(backend))?;
+        if !m.getattr("transport_list_registry")?.contains(prefix)? {
+            m.call_method1("register_transport_proto", (prefix,))?;
+        }
+        m.call_method1("register_transport", (prefix, class))?;
+        Ok(())
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -238,4 +931,256 @@ mod tests {
         let base = transport.base();
         assert!(base.to_string().starts_with("file://"));
     }
+
+    #[test]
+    fn test_transport_files() {
+        let td = tempfile::tempdir().unwrap();
+        let url = url::Url::from_directory_path(td.path()).unwrap();
+        let transport = get_transport(&url, None).unwrap();
+
+        transport.put_bytes("a", b"hello world", None).unwrap();
+        assert_eq!(transport.get_bytes("a").unwrap(), b"hello world");
+        assert_eq!(transport.append_bytes("a", b"!", None).unwrap(), 11);
+        let mut contents = Vec::new();
+        std::io::Read::read_to_end(&mut transport.get("a").unwrap(), &mut contents).unwrap();
+        assert_eq!(contents, b"hello world!");
+        assert_eq!(
+            transport.readv("a", &[(0, 5), (6, 5)]).unwrap(),
+            vec![(0, b"hello".to_vec()), (6, b"world".to_vec())]
+        );
+
+        let stat = transport.stat("a").unwrap();
+        assert!(stat.is_file());
+        assert_eq!(stat.size, 12);
+
+        transport.mkdir("dir", None).unwrap();
+        assert!(transport.stat("dir").unwrap().is_dir());
+        assert!(matches!(
+            transport.mkdir("dir", None),
+            Err(Error::FileExists(..))
+        ));
+        transport.rename("a", "dir/b").unwrap();
+        assert_eq!(transport.list_dir("dir").unwrap(), vec!["b".to_string()]);
+        assert_eq!(
+            transport.iter_files_recursive().unwrap(),
+            vec!["dir/b".to_string()]
+        );
+        assert!(matches!(
+            transport.get_bytes("a"),
+            Err(Error::NoSuchFile(..))
+        ));
+
+        transport.delete("dir/b").unwrap();
+        transport.rmdir("dir").unwrap();
+        assert!(!transport.has("dir").unwrap());
+    }
+
+    #[test]
+    fn test_transport_lock() {
+        let td = tempfile::tempdir().unwrap();
+        let url = url::Url::from_directory_path(td.path()).unwrap();
+        let transport = get_transport(&url, None).unwrap();
+
+        transport.put_bytes("lock", b"", None).unwrap();
+        let lock = transport.lock_write("lock").unwrap();
+        std::mem::drop(lock);
+        let lock = transport.lock_read("lock").unwrap();
+        std::mem::drop(lock);
+    }
+
+    pyo3::import_exception!(breezy.errors, DirectoryNotEmpty);
+
+    #[derive(Default)]
+    struct MemoryBackend {
+        files: Mutex<std::collections::BTreeMap<String, Vec<u8>>>,
+        dirs: Mutex<std::collections::BTreeSet<String>>,
+    }
+
+    impl MemoryBackend {
+        fn is_dir(&self, path: &str) -> bool {
+            path.is_empty() || self.dirs.lock().unwrap().contains(path)
+        }
+
+        fn check_parent(&self, path: &str) -> Result<(), Error> {
+            let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+            if self.is_dir(parent) {
+                Ok(())
+            } else {
+                Err(Error::NoSuchFile(PathBuf::from(parent)))
+            }
+        }
+
+        fn children(&self, path: &str) -> Vec<String> {
+            let prefix = join_path(path, "");
+            let files = self.files.lock().unwrap();
+            let dirs = self.dirs.lock().unwrap();
+            files
+                .keys()
+                .chain(dirs.iter())
+                .filter_map(|p| p.strip_prefix(&prefix))
+                .filter(|p| !p.contains('/'))
+                .map(|p| p.to_string())
+                .collect()
+        }
+    }
+
+    impl TransportBackend for MemoryBackend {
+        fn get_bytes(&self, path: &str) -> Result<Vec<u8>, Error> {
+            self.files
+                .lock()
+                .unwrap()
+                .get(path)
+                .cloned()
+                .ok_or_else(|| Error::NoSuchFile(PathBuf::from(path)))
+        }
+
+        fn put_bytes(&self, path: &str, data: &[u8], _mode: Option<u32>) -> Result<(), Error> {
+            self.check_parent(path)?;
+            self.files
+                .lock()
+                .unwrap()
+                .insert(path.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn mkdir(&self, path: &str, _mode: Option<u32>) -> Result<(), Error> {
+            if self.has(path)? {
+                return Err(Error::FileExists(PathBuf::from(path), None));
+            }
+            self.check_parent(path)?;
+            self.dirs.lock().unwrap().insert(path.to_string());
+            Ok(())
+        }
+
+        fn rmdir(&self, path: &str) -> Result<(), Error> {
+            if !self.is_dir(path) {
+                return Err(Error::NoSuchFile(PathBuf::from(path)));
+            }
+            if !self.children(path).is_empty() {
+                return Err(Error::Other(DirectoryNotEmpty::new_err(
+                    (path.to_string(),),
+                )));
+            }
+            self.dirs.lock().unwrap().remove(path);
+            Ok(())
+        }
+
+        fn delete(&self, path: &str) -> Result<(), Error> {
+            self.files
+                .lock()
+                .unwrap()
+                .remove(path)
+                .map(|_| ())
+                .ok_or_else(|| Error::NoSuchFile(PathBuf::from(path)))
+        }
+
+        fn rename(&self, from: &str, to: &str) -> Result<(), Error> {
+            if !self.has(from)? {
+                return Err(Error::NoSuchFile(PathBuf::from(from)));
+            }
+            if self.is_dir(to) {
+                if !self.children(to).is_empty() {
+                    return Err(Error::FileExists(PathBuf::from(to), None));
+                }
+                self.dirs.lock().unwrap().remove(to);
+            }
+            self.check_parent(to)?;
+            let rename = |path: &str| {
+                if path == from {
+                    Some(to.to_string())
+                } else {
+                    path.strip_prefix(&join_path(from, ""))
+                        .map(|rest| join_path(to, rest))
+                }
+            };
+            let mut files = self.files.lock().unwrap();
+            *files = std::mem::take(&mut *files)
+                .into_iter()
+                .map(|(path, data)| (rename(&path).unwrap_or(path), data))
+                .collect();
+            let mut dirs = self.dirs.lock().unwrap();
+            *dirs = std::mem::take(&mut *dirs)
+                .into_iter()
+                .map(|path| rename(&path).unwrap_or(path))
+                .collect();
+            Ok(())
+        }
+
+        fn list_dir(&self, path: &str) -> Result<Vec<String>, Error> {
+            if !self.is_dir(path) {
+                return Err(Error::NoSuchFile(PathBuf::from(path)));
+            }
+            Ok(self.children(path))
+        }
+
+        fn stat(&self, path: &str) -> Result<Stat, Error> {
+            if self.is_dir(path) {
+                return Ok(Stat {
+                    size: 0,
+                    mode: 0o040755,
+                });
+            }
+            match self.files.lock().unwrap().get(path) {
+                Some(data) => Ok(Stat {
+                    size: data.len() as u64,
+                    mode: 0o100644,
+                }),
+                None => Err(Error::NoSuchFile(PathBuf::from(path))),
+            }
+        }
+    }
+
+    #[test]
+    fn test_register_transport() {
+        register_transport("rust-memory://", Box::new(MemoryBackend::default())).unwrap();
+
+        let url: url::Url = "rust-memory:///".parse().unwrap();
+        let transport = get_transport(&url, None).unwrap();
+        assert_eq!(transport.base(), url);
+        transport.put_bytes("a", b"contents", None).unwrap();
+        assert_eq!(transport.get_bytes("a").unwrap(), b"contents");
+        transport.mkdir("dir", None).unwrap();
+        let sub = transport.clone("dir").unwrap();
+        assert_eq!(sub.base().as_str(), "rust-memory:///dir/");
+        sub.put_bytes("b", b"more", None).unwrap();
+        assert_eq!(
+            transport.iter_files_recursive().unwrap(),
+            vec!["a".to_string(), "dir/b".to_string()]
+        );
+        assert!(transport.stat("dir").unwrap().is_dir());
+        assert!(matches!(
+            transport.get_bytes("missing"),
+            Err(Error::NoSuchFile(..))
+        ));
+
+        let url: url::Url = "rust-memory:///branch/".parse().unwrap();
+        crate::controldir::create_branch_convenience_as_generic(
+            &url,
+            Some(false),
+            &crate::controldir::ControlDirFormat::default(),
+        )
+        .unwrap();
+        crate::controldir::open(&url, None).unwrap();
+        let branch = crate::branch::open_as_generic(&url).unwrap();
+        assert_eq!(
+            crate::branch::Branch::last_revision(&branch),
+            crate::RevisionId::null()
+        );
+    }
+
+    #[test]
+    fn test_register_transport_with_host() {
+        let backend = MemoryBackend::default();
+        backend.mkdir("dir", None).unwrap();
+        backend.put_bytes("dir/a", b"contents", None).unwrap();
+        register_transport("rust-bucket://bucket/", Box::new(backend)).unwrap();
+
+        let url: url::Url = "rust-bucket://bucket/".parse().unwrap();
+        let dir = get_transport(&url, None).unwrap().clone("dir").unwrap();
+        assert_eq!(dir.base().as_str(), "rust-bucket://bucket/dir/");
+        assert_eq!(dir.get_bytes("a").unwrap(), b"contents");
+        assert_eq!(dir.get_bytes("/dir/a").unwrap(), b"contents");
+        assert_eq!(dir.clone("..").unwrap().base(), url);
+        assert_eq!(dir.clone("../..").unwrap().base(), url);
+    }
 }
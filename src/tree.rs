@@ -89,7 +89,7 @@ pub enum SearchRuleType {
 }
 
 /// Represents a conflict in a tree.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Conflict {
     /// The path involved in the conflict.
     pub path: PathBuf,
@@ -1607,6 +1607,33 @@ impl PyTree for GenericTree {
     }
 }
 
+/// A generic mutable tree implementation that wraps any Python mutable tree object.
+pub struct GenericMutableTree(Py<PyAny>);
+
+impl<'py> IntoPyObject<'py> for GenericMutableTree {
+    type Target = PyAny;
+    type Output = Bound<'py, Self::Target>;
+    type Error = std::convert::Infallible;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        Ok(self.0.into_bound(py))
+    }
+}
+
+impl From<Py<PyAny>> for GenericMutableTree {
+    fn from(obj: Py<PyAny>) -> Self {
+        GenericMutableTree(obj)
+    }
+}
+
+impl PyTree for GenericMutableTree {
+    fn to_object(&self, py: Python) -> Py<PyAny> {
+        self.0.clone_ref(py)
+    }
+}
+
+impl PyMutableTree for GenericMutableTree {}
+
 /// Trait for trees that support modification operations.
 pub trait MutableTree: Tree {
     /// Add specified files to version control.
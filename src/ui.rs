@@ -101,6 +101,225 @@ impl std::fmt::Debug for SilentUIFactory {
     }
 }
 
+/// Direction of transport activity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportDirection {
+    /// Data was read.
+    Read,
+    /// Data was written.
+    Write,
+}
+
+/// A snapshot of the state of a progress task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressTask {
+    /// Identifier of the task, unique while the task is active.
+    pub id: usize,
+    /// Identifier of the task this task is nested in, if any.
+    pub parent: Option<usize>,
+    /// The current message of the task.
+    pub message: Option<String>,
+    /// The number of items processed so far, if known.
+    pub current: Option<u64>,
+    /// The total number of items, if known.
+    pub total: Option<u64>,
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for ProgressTask {
+    type Error = PyErr;
+
+    fn extract(ob: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        let parent = ob.getattr("_parent_task")?;
+        Ok(ProgressTask {
+            id: ob.as_ptr() as usize,
+            parent: if parent.is_none() {
+                None
+            } else {
+                Some(parent.as_ptr() as usize)
+            },
+            message: ob.getattr("msg")?.extract()?,
+            current: ob.getattr("current_cnt")?.extract()?,
+            total: ob.getattr("total_cnt")?.extract()?,
+        })
+    }
+}
+
+/// Receives progress and user interaction events from Breezy.
+///
+/// All methods have default implementations that ignore the event, so
+/// implementations only need to override the events they are interested in.
+pub trait ProgressReporter: Send + Sync {
+    /// A new (possibly nested) progress task was started.
+    fn task_started(&self, _task: &ProgressTask) {}
+
+    /// The message or counts of a progress task changed.
+    fn task_updated(&self, _task: &ProgressTask) {}
+
+    /// A progress task finished.
+    fn task_finished(&self, _task: &ProgressTask) {}
+
+    /// Bytes were transferred by a transport.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte_count` - The number of bytes transferred in this operation
+    /// * `direction` - Whether the bytes were read or written
+    fn transport_activity(&self, _byte_count: u64, _direction: TransportDirection) {}
+
+    /// Show an informational message to the user.
+    fn show_message(&self, _msg: &str) {}
+
+    /// Show a warning to the user.
+    fn show_warning(&self, _msg: &str) {}
+
+    /// Show an error to the user.
+    fn show_error(&self, _msg: &str) {}
+
+    /// Ask the user to confirm an action.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The question to ask
+    /// * `confirmation_id` - Identifier of the kind of confirmation, e.g.
+    ///   `breezy.lockdir.break`, or `None` for a plain yes/no question
+    ///
+    /// # Returns
+    ///
+    /// Whether the action should go ahead. Defaults to `false`.
+    fn confirm_action(&self, _prompt: &str, _confirmation_id: Option<&str>) -> bool {
+        false
+    }
+}
+
+/// UI factory that forwards progress and messages to a [`ProgressReporter`].
+///
+/// Anything not covered by the reporter behaves like [`SilentUIFactory`].
+pub struct ProgressUIFactory(Py<PyAny>);
+
+fn override_method<R>(
+    factory: &Bound<PyAny>,
+    name: &str,
+    f: impl Fn(&Bound<pyo3::types::PyTuple>, Option<&Bound<pyo3::types::PyDict>>) -> PyResult<R>
+        + Send
+        + Sync
+        + 'static,
+) -> PyResult<()>
+where
+    R: for<'py> IntoPyObject<'py>,
+{
+    let py = factory.py();
+    let f = pyo3::types::PyCFunction::new_closure(py, None, None, f)?;
+    factory.setattr(name, f)?;
+    Ok(())
+}
+
+impl ProgressUIFactory {
+    /// Create a new UI factory reporting to `reporter`.
+    pub fn new(reporter: std::sync::Arc<dyn ProgressReporter>) -> Self {
+        Python::attach(|py| Self::create(py, reporter).unwrap())
+    }
+
+    fn create(py: Python, reporter: std::sync::Arc<dyn ProgressReporter>) -> PyResult<Self> {
+        let factory = py
+            .import("breezy.ui")?
+            .getattr("SilentUIFactory")?
+            .call0()?;
+
+        let orig = factory.getattr("nested_progress_bar")?.unbind();
+        let r = reporter.clone();
+        override_method(&factory, "nested_progress_bar", move |_args, _kwargs| {
+            Python::attach(|py| {
+                let task = orig.call0(py)?;
+                r.task_started(&task.extract(py)?);
+                Ok(task)
+            })
+        })?;
+
+        let orig = factory.getattr("_progress_updated")?.unbind();
+        let r = reporter.clone();
+        override_method(&factory, "_progress_updated", move |args, _kwargs| {
+            let task = args.get_item(0)?;
+            orig.call1(args.py(), (&task,))?;
+            r.task_updated(&task.extract()?);
+            Ok(())
+        })?;
+
+        let orig = factory.getattr("_progress_finished")?.unbind();
+        let r = reporter.clone();
+        override_method(&factory, "_progress_finished", move |args, _kwargs| {
+            let task = args.get_item(0)?;
+            let snapshot = task.extract()?;
+            orig.call1(args.py(), (&task,))?;
+            r.task_finished(&snapshot);
+            Ok(())
+        })?;
+
+        let r = reporter.clone();
+        override_method(
+            &factory,
+            "report_transport_activity",
+            move |args, _kwargs| {
+                let (_transport, byte_count, direction): (Bound<PyAny>, u64, String) =
+                    args.extract()?;
+                let direction = if direction == "write" {
+                    TransportDirection::Write
+                } else {
+                    TransportDirection::Read
+                };
+                r.transport_activity(byte_count, direction);
+                Ok(())
+            },
+        )?;
+
+        for (name, show) in [
+            (
+                "show_message",
+                ProgressReporter::show_message as fn(&_, &str),
+            ),
+            ("show_warning", ProgressReporter::show_warning),
+            ("show_error", ProgressReporter::show_error),
+        ] {
+            let r = reporter.clone();
+            override_method(&factory, name, move |args, _kwargs| {
+                let msg = args.get_item(0)?.str()?.to_string();
+                show(r.as_ref(), &msg);
+                Ok(())
+            })?;
+        }
+
+        let r = reporter.clone();
+        override_method(&factory, "confirm_action", move |args, _kwargs| {
+            let prompt = args.get_item(0)?;
+            let confirmation_id: String = args.get_item(1)?.extract()?;
+            let prompt = match args.get_item(2) {
+                Ok(kwargs) if !kwargs.is_none() => prompt.rem(kwargs)?,
+                _ => prompt,
+            };
+            Ok(r.confirm_action(&prompt.str()?.to_string(), Some(&confirmation_id)))
+        })?;
+
+        let r = reporter;
+        override_method(&factory, "get_boolean", move |args, _kwargs| {
+            let prompt = args.get_item(0)?.str()?.to_string();
+            Ok(r.confirm_action(&prompt, None))
+        })?;
+
+        Ok(ProgressUIFactory(factory.unbind()))
+    }
+}
+
+impl PyUIFactory for ProgressUIFactory {
+    fn to_object(&self, py: Python) -> Py<PyAny> {
+        self.0.clone_ref(py)
+    }
+}
+
+impl std::fmt::Debug for ProgressUIFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_fmt(format_args!("ProgressUIFactory({:?})", self.0))
+    }
+}
+
 /// Install a UI factory globally.
 pub fn install_ui_factory(factory: &dyn PyUIFactory) {
     Python::attach(|py| {
@@ -136,6 +355,56 @@ mod tests {
         let _ = SilentUIFactory::new();
     }
 
+    #[derive(Default)]
+    struct RecordingReporter(std::sync::Mutex<Vec<String>>);
+
+    impl ProgressReporter for RecordingReporter {
+        fn task_started(&self, _task: &ProgressTask) {
+            self.0.lock().unwrap().push("started".to_string());
+        }
+
+        fn task_updated(&self, task: &ProgressTask) {
+            self.0.lock().unwrap().push(format!(
+                "updated {} {:?}/{:?}",
+                task.message.as_deref().unwrap_or(""),
+                task.current,
+                task.total
+            ));
+        }
+
+        fn task_finished(&self, _task: &ProgressTask) {
+            self.0.lock().unwrap().push("finished".to_string());
+        }
+
+        fn show_warning(&self, msg: &str) {
+            self.0.lock().unwrap().push(format!("warning {}", msg));
+        }
+    }
+
+    #[test]
+    fn test_progress_factory() {
+        let reporter = std::sync::Arc::new(RecordingReporter::default());
+        let factory = ProgressUIFactory::new(reporter.clone());
+        Python::attach(|py| {
+            let factory = factory.to_object(py);
+            let task = factory.call_method0(py, "nested_progress_bar").unwrap();
+            task.call_method1(py, "update", ("fetching", 1, 3)).unwrap();
+            task.call_method0(py, "finished").unwrap();
+            factory
+                .call_method1(py, "show_warning", ("careful",))
+                .unwrap();
+        });
+        assert_eq!(
+            *reporter.0.lock().unwrap(),
+            vec![
+                "started",
+                "updated fetching Some(1)/Some(3)",
+                "finished",
+                "warning careful"
+            ]
+        );
+    }
+
     #[test]
     fn test_run_with_silent_factory() {
         with_silent_ui_factory(|| {
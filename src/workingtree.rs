@@ -245,7 +245,7 @@ pub trait WorkingTree: MutableTree {
     fn get_reference_info(&self, path: &Path) -> Result<Option<(String, PathBuf)>, Error>;
 
     /// Get the shelf manager.
-    fn get_shelf_manager(&self) -> Result<Py<PyAny>, Error>;
+    fn get_shelf_manager(&self) -> Result<crate::shelf::ShelfManager, Error>;
 
     /// Get ignored files.
     fn ignored_files(&self) -> Result<Vec<PathBuf>, Error>;
@@ -731,8 +731,12 @@ impl<T: ?Sized + PyWorkingTree> WorkingTree for T {
         })
     }
 
-    fn get_shelf_manager(&self) -> Result<Py<PyAny>, Error> {
-        Python::attach(|py| Ok(self.to_object(py).call_method0(py, "get_shelf_manager")?))
+    fn get_shelf_manager(&self) -> Result<crate::shelf::ShelfManager, Error> {
+        Python::attach(|py| {
+            Ok(crate::shelf::ShelfManager::from(
+                self.to_object(py).call_method0(py, "get_shelf_manager")?,
+            ))
+        })
     }
 
     fn ignored_files(&self) -> Result<Vec<PathBuf>, Error> {